@@ -4,6 +4,7 @@ use schemars::schema::Schema;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use typetag::__private::erased_serde;
 use crate::inline_dyn::{Dynamic, DynamicFrom};
+use crate::language_detection::detection::LanguageHint;
 use crate::tokenizer::token_stream::TokenStream;
 use crate::tokenizer::{BoxableTokenizer, BoxTokenizer, Tokenizer};
 
@@ -24,6 +25,12 @@ pub trait Analyzer: 'static + Send + Sync + Clone  {
     where
         Self: 'token;
     fn analyze<'token>(&'token self, text: &'token str) -> Self::TokenStream<'token>;
+
+    /// Analyzes a text with what is known of its language ahead, e.g. from
+    /// metadata. Analyzers without language detection ignore the hint.
+    fn analyze_with_hints<'token>(&'token self, text: &'token str, _hint: &'token LanguageHint) -> Self::TokenStream<'token> {
+        self.analyze(text)
+    }
 }
 
 impl Analyzer for BoxAnalyzer {
@@ -32,6 +39,11 @@ impl Analyzer for BoxAnalyzer {
     fn analyze<'token>(&'token self, text: &'token str) -> Self::TokenStream<'token> {
         self.0.box_analyze(text.into())
     }
+
+    fn analyze_with_hints<'token>(&'token self, text: &'token str, hint: &'token LanguageHint) -> Self::TokenStream<'token> {
+        self.0.box_analyze_with_hints(text, hint)
+    }
+
     fn type_name<'a>(&self) -> &'static str {
         &self.0.type_name()
     }
@@ -45,6 +57,7 @@ impl Clone for BoxAnalyzer {
 
 pub trait BoxableAnalyzer: 'static + Send + Sync + typetag::Serialize  {
     fn box_analyze<'token>(&'token self, text: &'token str) -> Dynamic<dyn TokenStream<'token> + 'token>;
+    fn box_analyze_with_hints<'token>(&'token self, text: &'token str, hint: &'token LanguageHint) -> Dynamic<dyn TokenStream<'token> + 'token>;
     fn box_clone(&self) -> Box<dyn BoxableAnalyzer>;
     fn type_name<'a>(&self) -> &'static str;
 }
@@ -55,6 +68,10 @@ impl<T: Analyzer + Serialize> BoxableAnalyzer for T
         DynamicFrom::from(self.analyze(text))
     }
 
+    fn box_analyze_with_hints<'token>(&'token self, text: &'token str, hint: &'token LanguageHint) -> Dynamic<dyn TokenStream<'token> + 'token> {
+        DynamicFrom::from(self.analyze_with_hints(text, hint))
+    }
+
     fn box_clone(&self) -> Box<dyn BoxableAnalyzer> {
         Box::new(self.clone())
     }
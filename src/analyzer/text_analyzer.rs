@@ -10,7 +10,8 @@ use crate::char_filter::character_filter_layer::{BoxCharFilterLayer, CharacterFi
 use crate::{language_detection, tokenizer};
 use crate::analyzer::Analyzer;
 use crate::char_filter::{BoxCharacterFilter, CharacterFilter};
-use crate::language_detection::detection::LanguageDetection;
+use crate::char_filter::offset_mapping::OffsetMapping;
+use crate::language_detection::detection::{HintedLanguageDetections, LanguageDetection, LanguageHint};
 use crate::language_detection::{BoxableLanguageDetector, BoxLanguageDetector, MultipleLanguageDetector};
 use crate::token::BorrowedToken;
 use crate::token_filter::token_filter_layer::{BoxTokenFilterLayer, TokenFilterLayers};
@@ -28,6 +29,10 @@ pub struct TextAnalyzer<
 > {
     pub character_filters: CharacterFilters,
     pub language_detector: LanguageDetector,
+    /// Character filters applied to each language segment before tokenization,
+    /// with the language and the script of the segment known.
+    #[serde(default)]
+    pub segment_character_filters: Option<BoxCharFilterLayer>,
     pub tokenizer: Tokenizer,
     pub token_filters: TokenFilters,
 }
@@ -48,13 +53,20 @@ impl<
     AnalyzerStream<'token, CharacterFilters, LanguageDetector, Tokenizer, TokenFilters>;
 
     fn analyze<'a>(&'a self, text: &'a str) -> Self::TokenStream<'a> {
+        self.analyze_with_hints(text, &LanguageHint::None)
+    }
+
+    fn analyze_with_hints<'a>(&'a self, text: &'a str, hint: &'a LanguageHint) -> Self::TokenStream<'a> {
         AnalyzerStream {
-            text: text,
+            text,
+            hint,
             token_filters: &self.token_filters,
             character_filters: &self.character_filters,
             language_detector: &self.language_detector,
+            segment_character_filters: self.segment_character_filters.as_ref(),
             tokenizer: &self.tokenizer,
             inner_stream: None,
+            offsets: OffsetMapping::new(),
         }
     }
 
@@ -71,20 +83,22 @@ pub struct AnalyzerStream<
     TokenFilters: TokenFilterLayers,
 > {
     text: &'stream str,
+    hint: &'stream LanguageHint,
     character_filters: &'stream CharacterFilters,
     language_detector: &'stream LanguageDetector,
+    segment_character_filters: Option<&'stream BoxCharFilterLayer>,
     inner_stream: Option<
         OwningHandle<
             Cow<'stream, str>,
             TokenizerStream<
-                'stream,
-                LanguageDetector::LanguageDetections<'stream, 'stream>,
-                TokenFilters::TokenStream<'stream, Tokenizer::TokenStream<'stream>>,
+                TokenFilters::TokenStream<'stream, SegmentedTokenStream<'stream, HintedLanguageDetections<'stream, 'stream, LanguageDetector::LanguageDetections<'stream, 'stream>>, Tokenizer>>,
             >,
         >,
     >,
     tokenizer: &'stream Tokenizer,
     token_filters: &'stream TokenFilters,
+    // replacements of the character filters, to report offsets in the original text.
+    offsets: OffsetMapping,
 }
 
 impl<
@@ -100,71 +114,123 @@ for AnalyzerStream<'stream, CharacterFilters, LanguageDetector, Tokenizer, Token
         loop {
             match self.inner_stream {
                 None => {
-                    let text = self.character_filters.apply_layer(Cow::Borrowed(self.text));
-                    let owning_ref = OwningHandle::new_with_fn(text, |text| TokenizerStream {
-                        language_detections: self
-                            .language_detector
-                            .detect_multiple_languages(unsafe { &*text }),
-                        token_stream: None,
+                    let text = self.character_filters.apply_layer_with_offsets(Cow::Borrowed(self.text), &mut self.offsets);
+                    let owning_ref = OwningHandle::new_with_fn(text, |text| {
+                        let text = unsafe { &*text };
+                        let segments = SegmentedTokenStream {
+                            text,
+                            tokenizer: self.tokenizer,
+                            language_detections: self.language_detector.detect_multiple_languages_with_hint(text, self.hint),
+                            character_filters: self.segment_character_filters,
+                            token_stream: None,
+                            segment_offsets: OffsetMapping::new(),
+                            segment_offset: 0,
+                            segment_position: 0,
+                            next_position: 0,
+                        };
+                        TokenizerStream {
+                            token_stream: self.token_filters.apply_layer(segments),
+                        }
                     });
                     self.inner_stream = Some(owning_ref);
                 }
                 Some(ref mut analyzer) => {
-                    let mut analyzer = analyzer;
-                    polonius!(|analyzer| -> Option<BorrowedToken<'polonius, 'stream>> {
-                        if let Some(token) =
-                            analyzer.token_stream.as_mut().and_then(TokenStream::next)
-                        {
-                            polonius_return!(Some(token));
-                        }
-                    });
-                    match analyzer.language_detections.next() {
-                        None => return None,
-                        Some(detection) => {
-                            let token_stream = self.tokenizer.tokenize(detection);
-                            let token_stream = self.token_filters.apply_layer(token_stream);
-                            analyzer.token_stream = Some(token_stream);
-
-                            return analyzer.token_stream.as_mut().and_then(TokenStream::next);
-                        }
+                    let mut token = analyzer.token_stream.next()?;
+                    if !self.offsets.is_empty() {
+                        token.offset_from = self.offsets.correct_start(token.offset_from);
+                        token.offset_to = self.offsets.correct_end(token.offset_to);
                     }
+                    return Some(token);
                 }
             }
         }
     }
 }
 
-impl<
-    'analyzer,
-    LanguageDetections: Iterator<Item = LanguageDetection<'analyzer, 'analyzer>>,
-    TokenStream: tokenizer::token_stream::TokenStream<'analyzer>,
-> DerefMut for TokenizerStream<'analyzer, LanguageDetections, TokenStream>
-{
+impl<TokenStream> DerefMut for TokenizerStream<TokenStream> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self
     }
 }
 
-impl<
-    'analyzer,
-    LanguageDetections: Iterator<Item = LanguageDetection<'analyzer, 'analyzer>>,
-    TokenStream: tokenizer::token_stream::TokenStream<'analyzer>,
-> Deref for TokenizerStream<'analyzer, LanguageDetections, TokenStream>
-{
-    type Target = TokenizerStream<'analyzer, LanguageDetections, TokenStream>;
+impl<TokenStream> Deref for TokenizerStream<TokenStream> {
+    type Target = TokenizerStream<TokenStream>;
 
     fn deref(&self) -> &Self::Target {
         self
     }
 }
 
-struct TokenizerStream<
+struct TokenizerStream<TokenStream> {
+    token_stream: TokenStream,
+}
+
+/// Tokens of all the language segments of a text as one stream, so that token
+/// filters see the whole text. Offsets and positions are relative to the text.
+pub struct SegmentedTokenStream<
     'analyzer,
     LanguageDetections: Iterator<Item = LanguageDetection<'analyzer, 'analyzer>>,
-    TokenStream: tokenizer::token_stream::TokenStream<'analyzer>,
+    Tokenizer: tokenizer::Tokenizer,
 > {
+    text: &'analyzer str,
+    tokenizer: &'analyzer Tokenizer,
     language_detections: LanguageDetections,
-    token_stream: Option<TokenStream>,
+    character_filters: Option<&'analyzer BoxCharFilterLayer>,
+    // the segment text, filtered by the segment character filters, and its tokens.
+    token_stream: Option<OwningHandle<Cow<'analyzer, str>, TokenizerStream<Tokenizer::TokenStream<'analyzer>>>>,
+    // replacements of the segment character filters.
+    segment_offsets: OffsetMapping,
+    segment_offset: usize,
+    segment_position: usize,
+    next_position: usize,
+}
+
+impl<
+    'analyzer,
+    LanguageDetections: Iterator<Item = LanguageDetection<'analyzer, 'analyzer>>,
+    Tokenizer: tokenizer::Tokenizer,
+> TokenStream<'analyzer> for SegmentedTokenStream<'analyzer, LanguageDetections, Tokenizer>
+{
+    fn next<'this>(&'this mut self) -> Option<BorrowedToken<'this, 'analyzer>> {
+        let mut this = self;
+        loop {
+            polonius!(|this| -> Option<BorrowedToken<'polonius, 'analyzer>> {
+                if let Some(mut token) = this.token_stream.as_mut().and_then(|segment| segment.token_stream.next()) {
+                    if !this.segment_offsets.is_empty() {
+                        token.offset_from = this.segment_offsets.correct_start(token.offset_from);
+                        token.offset_to = this.segment_offsets.correct_end(token.offset_to);
+                    }
+                    token.offset_from += this.segment_offset;
+                    token.offset_to += this.segment_offset;
+                    token.position += this.segment_position;
+                    this.next_position = this.next_position.max(token.position + 1);
+                    polonius_return!(Some(token));
+                }
+            });
+
+            let detection = this.language_detections.next()?;
+            // segments are slices of the text.
+            this.segment_offset = (detection.text().as_ptr() as usize).saturating_sub(this.text.as_ptr() as usize);
+            this.segment_position = this.next_position;
+            this.segment_offsets = OffsetMapping::new();
+            let tokenizer = this.tokenizer;
+            let (text, detection) = match this.character_filters {
+                Some(filters) => {
+                    let (language, script) = ((*detection.language).clone(), *detection.script);
+                    let text = filters.apply_layer_to_segment(Cow::Borrowed(detection.text()), language.language, script, &mut this.segment_offsets);
+                    (text, Err((language, script)))
+                }
+                None => (Cow::Borrowed(detection.text()), Ok(detection)),
+            };
+            this.token_stream = Some(OwningHandle::new_with_fn(text, |text| {
+                let text = unsafe { &*text };
+                let detection = detection.unwrap_or_else(|(language, script)| LanguageDetection::new_detected(text, language, script));
+                TokenizerStream {
+                    token_stream: tokenizer.tokenize(detection),
+                }
+            }));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -192,7 +258,8 @@ mod tests {
 
         let mut analyzer = TextAnalyzer {
             character_filters: crate::char_filter::character_filter_layer::BaseLevel,
-            language_detector: WhichLangDetector{},
+            language_detector: WhichLangDetector::default(),
+            segment_character_filters: None,
             tokenizer: tokenizer,
             token_filters: dynm,
         };
@@ -226,7 +293,8 @@ mod tests {
 
         let mut analyzer = TextAnalyzer {
             character_filters: crate::char_filter::character_filter_layer::BaseLevel,
-            language_detector: WhichLangDetector{},
+            language_detector: WhichLangDetector::default(),
+            segment_character_filters: None,
             tokenizer: tokenizer,
             token_filters: dynm,
         };
@@ -261,6 +329,108 @@ mod tests {
         let result = serde_json::to_string(&layers).unwrap();
         println!("{:#}", result);
     }
+
+    #[test]
+    fn segment_character_filters() {
+        use std::collections::{BTreeSet, HashSet};
+        use crate::char_filter::character_filter_layer::BaseLevel as CharBaseLevel;
+        use crate::char_filter::conditional_character_filter::ConditionalCharFilter;
+        use crate::char_filter::mapping_character_filter::{MappingCharFilter, MappingConfig};
+        use crate::language_detection::LanguageDetector;
+        use crate::language_detection::whatlang::WhatLangDetector;
+        use crate::script::Script;
+        use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+
+        let accents = MappingCharFilter::new(MappingConfig {
+            mappings: BTreeMap::from([("έ".to_string(), "ε".to_string()), ("ό".to_string(), "ο".to_string())]),
+            mapping_files: vec![],
+        }).unwrap();
+        let greek = ConditionalCharFilter {
+            languages: BTreeSet::new(),
+            scripts: HashSet::from([Script::Greek]),
+            filters: CharBaseLevel.wrap_dynamic_layer(accents),
+        };
+        let analyzer = TextAnalyzer {
+            character_filters: CharBaseLevel,
+            language_detector: WhichLangDetector::default(),
+            segment_character_filters: Some(CharBaseLevel.wrap_dynamic_layer(greek.clone())),
+            tokenizer: WhitespaceTokenizer {},
+            token_filters: BaseLevel,
+        };
+        fn tokens(analyzer: &impl Analyzer, text: &str) -> Vec<(String, String)> {
+            analyzer.analyze(text)
+                .as_iter()
+                .map(|token| (token.text, text[token.offset_from..token.offset_to].to_string()))
+                .collect()
+        }
+        let text = "Καλημέρα κόσμε";
+        let expected = [
+            ("Καλημερα".to_string(), "Καλημέρα".to_string()),
+            ("κοσμε".to_string(), "κόσμε".to_string()),
+        ];
+        assert_eq!(tokens(&analyzer, text), expected);
+
+        let serialized = serde_json::to_string(&BoxAnalyzer::new(analyzer.clone())).unwrap();
+        assert!(serialized.contains("segment_character_filters"));
+        let deserialized: BoxAnalyzer = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(tokens(&deserialized, text), expected);
+
+        let analyzer = TextAnalyzer { segment_character_filters: None, ..analyzer };
+        let serialized = serde_json::to_string(&BoxAnalyzer::new(analyzer)).unwrap();
+        assert!(!serialized.contains("segment_character_filters"));
+        assert!(serde_json::from_str::<BoxAnalyzer>(&serialized).is_ok());
+
+        // the filtered segments keep the confidence of their detection.
+        let analyzer = TextAnalyzer {
+            character_filters: CharBaseLevel,
+            language_detector: WhatLangDetector::default(),
+            segment_character_filters: Some(CharBaseLevel.wrap_dynamic_layer(greek)),
+            tokenizer: WhitespaceTokenizer {},
+            token_filters: BaseLevel,
+        };
+        let text = "The Weather In London";
+        let confidence = WhatLangDetector::default().detect_lang_with_confidence(text)[0].1;
+        assert!(confidence < 1.0);
+        assert!(analyzer.analyze(text).as_iter().all(|token| token.language_confidence() == confidence));
+    }
+
+    #[test]
+    fn language_hints() {
+        use std::collections::BTreeSet;
+        use crate::language::Language;
+        use crate::language_detection::fixed::FixedLanguageDetector;
+        use crate::language_detection::whatlang::WhatLangDetector;
+        use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+
+        let analyzer = TextAnalyzer {
+            character_filters: crate::char_filter::character_filter_layer::BaseLevel,
+            language_detector: WhatLangDetector::default(),
+            segment_character_filters: None,
+            tokenizer: WhitespaceTokenizer {},
+            token_filters: BaseLevel,
+        };
+        fn languages(analyzer: &impl Analyzer, text: &str, hint: &LanguageHint) -> Vec<Language> {
+            analyzer.analyze_with_hints(text, hint).as_iter().map(|token| token.language()).collect()
+        }
+        let text = "El perro corre rápidamente por la calle";
+
+        assert!(languages(&analyzer, text, &LanguageHint::None).iter().all(|&language| language == Language::Spa));
+        assert!(languages(&analyzer, text, &LanguageHint::Language(Language::Fra)).iter().all(|&language| language == Language::Fra));
+        let allowed = BTreeSet::from([Language::Por, Language::Ita]);
+        assert!(languages(&analyzer, text, &LanguageHint::AllowList(allowed.clone())).iter().all(|language| allowed.contains(language)));
+
+        let boxed = BoxAnalyzer::new(analyzer.clone());
+        assert!(languages(&boxed, text, &LanguageHint::Language(Language::Deu)).iter().all(|&language| language == Language::Deu));
+
+        let analyzer = TextAnalyzer {
+            character_filters: crate::char_filter::character_filter_layer::BaseLevel,
+            language_detector: FixedLanguageDetector { language: Language::Cat, script: None },
+            segment_character_filters: None,
+            tokenizer: WhitespaceTokenizer {},
+            token_filters: BaseLevel,
+        };
+        assert!(languages(&analyzer, text, &LanguageHint::None).iter().all(|&language| language == Language::Cat));
+    }
 }
 
 
@@ -272,9 +442,13 @@ impl<
 > SerdeSerialize for TextAnalyzer<CharacterFilters, LanguageDetector, Tokenizer, TokenFilters>
 {
     default fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-        let mut struct_serializer = serializer.serialize_struct("TextAnalyzer", 4)?;
+        let mut struct_serializer = serializer.serialize_struct("TextAnalyzer", 5)?;
         struct_serializer.serialize_field("character_filters", &self.character_filters as &dyn crate::char_filter::character_filter_layer::BoxableLayer)?;
         struct_serializer.serialize_field("language_detector", &self.language_detector as &dyn BoxableLanguageDetector)?;
+        match &self.segment_character_filters {
+            Some(filters) => struct_serializer.serialize_field("segment_character_filters", filters)?,
+            None => struct_serializer.skip_field("segment_character_filters")?,
+        }
         struct_serializer.serialize_field("tokenizer", &self.tokenizer as &dyn BoxableTokenizer)?;
         struct_serializer.serialize_field("token_filters", &self.token_filters as &dyn crate::token_filter::token_filter_layer::BoxableLayer)?;
         struct_serializer.end()
@@ -9,6 +9,9 @@ use typetag::__private::schemars::JsonSchema;
 use typetag::__private::schemars::schema::{ArrayValidation, InstanceType, Schema, SchemaObject};
 use crate::char_filter::{BoxableCharacterFilter, BoxCharacterFilter, CharacterFilter};
 use crate::char_filter::CharacterFilterRegistry;
+use crate::char_filter::offset_mapping::OffsetMapping;
+use crate::language::Language;
+use crate::script::Script;
 
 
 #[derive(Clone)]
@@ -32,6 +35,14 @@ impl CharacterFilter for BoxCharFilterLayer
     fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
         <Self as CharacterFilterLayers>::apply_layer(self, text)
     }
+
+    fn apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        <Self as CharacterFilterLayers>::apply_layer_with_offsets(self, text, offsets)
+    }
+
+    fn apply_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        <Self as CharacterFilterLayers>::apply_layer_to_segment(self, text, language, script, offsets)
+    }
 }
 
 impl JsonSchema for BoxCharFilterLayer {
@@ -67,6 +78,20 @@ impl<F: CharacterFilter, L: CharacterFilterLayers> CharacterFilterLayers for Cha
         self.filter.apply(result)
     }
 
+    fn apply_layer_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        let result = self.upper_layer.apply_layer_with_offsets(text, offsets);
+        offsets.begin_stage();
+
+        self.filter.apply_with_offsets(result, offsets)
+    }
+
+    fn apply_layer_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        let result = self.upper_layer.apply_layer_to_segment(text, language, script, offsets);
+        offsets.begin_stage();
+
+        self.filter.apply_to_segment(result, language, script, offsets)
+    }
+
     default fn inspect_layer(&self, fun: &mut dyn FnMut(&dyn BoxableCharacterFilter)) {
         self.upper_layer.inspect_layer(fun);
     }
@@ -85,6 +110,8 @@ impl<F: CharacterFilter + Serialize, L: CharacterFilterLayers> CharacterFilterLa
 
 pub trait BoxableLayer: Send + Sync{
     fn box_apply_layer<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str>;
+    fn box_apply_layer_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str>;
+    fn box_apply_layer_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str>;
     fn box_inspect_layer<'a>(&self, fun: &mut dyn FnMut(&dyn BoxableCharacterFilter));
     fn box_clone(&self) -> BoxCharFilterLayer;
 }
@@ -94,6 +121,14 @@ impl<T: CharacterFilterLayers> BoxableLayer for T {
         self.apply_layer(text)
     }
 
+    fn box_apply_layer_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        self.apply_layer_with_offsets(text, offsets)
+    }
+
+    fn box_apply_layer_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        self.apply_layer_to_segment(text, language, script, offsets)
+    }
+
     fn box_inspect_layer<'a>(&self, fun: &mut dyn FnMut(&dyn BoxableCharacterFilter) ) {
         self.inspect_layer(fun)
     }
@@ -108,6 +143,14 @@ impl CharacterFilterLayers for BoxCharFilterLayer {
         self.0.box_apply_layer(text)
     }
 
+    fn apply_layer_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        self.0.box_apply_layer_with_offsets(text, offsets)
+    }
+
+    fn apply_layer_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        self.0.box_apply_layer_to_segment(text, language, script, offsets)
+    }
+
     fn inspect_layer(&self, fun: &mut dyn FnMut(&dyn BoxableCharacterFilter)) {
         self.0.box_inspect_layer(fun)
     }
@@ -115,7 +158,7 @@ impl CharacterFilterLayers for BoxCharFilterLayer {
 
 impl Clone for BoxCharFilterLayer {
     fn clone(&self) -> Self {
-        BoxableLayer::box_clone(self)
+        self.0.box_clone()
     }
 }
 
@@ -127,6 +170,10 @@ pub trait CharacterFilterLayers: 'static + Clone + Send + Sync{
         BoxCharFilterLayer::new(self)
     }
     fn apply_layer<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str>;
+    /// Applies the layers, recording the replacements the filters made in `offsets`.
+    fn apply_layer_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str>;
+    /// Applies the layers to a language segment of the text, after language detection.
+    fn apply_layer_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str>;
     fn wrap_layer<F: CharacterFilter>(self, filter: F) -> CharacterFilterLayer<F,Self>{
         CharacterFilterLayer {
             filter,
@@ -147,6 +194,14 @@ impl CharacterFilterLayers for BaseLevel {
         text
     }
 
+    fn apply_layer_with_offsets<'a>(&self, text: Cow<'a, str>, _offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        text
+    }
+
+    fn apply_layer_to_segment<'a>(&self, text: Cow<'a, str>, _language: Language, _script: Script, _offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        text
+    }
+
     fn inspect_layer(&self, fun: &mut dyn FnMut(&dyn BoxableCharacterFilter)) {
     }
 }
@@ -199,28 +254,18 @@ impl<'de> Deserialize<'de> for Box<dyn BoxableLayer> {
 #[cfg(test)]
 mod tests {
     use std::mem::size_of_val;
-    use regex::Regex;
-    use crate::char_filter::regex_character_filter::RegexCharacterFilter;
+    use crate::char_filter::regex_character_filter::{RegexCharacterFilter, RegexConfig};
     use super::*;
 
     #[test]
     fn char_filter_layers() {
         let base = BaseLevel;
         let base = base.dynamic();
-        let regex = BoxCharacterFilter(Box::new(RegexCharacterFilter{
-            pattern: Regex::new(r"foo").unwrap(),
-            replacement: "+".to_string()
-        }));
+        let regex = BoxCharacterFilter(Box::new(RegexCharacterFilter::new(RegexConfig::new("foo", "+")).unwrap()));
         let a = base.wrap_layer(regex);
-        let c = a.wrap_layer(RegexCharacterFilter{
-            pattern: Regex::new(r"bar").unwrap(),
-            replacement: "-".to_string()
-        });
-
-        let dynm = c.wrap_dynamic_layer(RegexCharacterFilter{
-            pattern: Regex::new(r"r").unwrap(),
-            replacement: "0".to_string()
-        });
+        let c = a.wrap_layer(RegexCharacterFilter::new(RegexConfig::new("bar", "-")).unwrap());
+
+        let dynm = c.wrap_dynamic_layer(RegexCharacterFilter::new(RegexConfig::new("r", "0")).unwrap());
         let result = dynm.apply_layer("foobarfoorbar".into());
 
 
@@ -232,20 +277,11 @@ mod tests {
     fn char_filter_layers_serialize() {
         let base = BaseLevel;
         let base = base.dynamic();
-        let regex = RegexCharacterFilter{
-            pattern: Regex::new(r"foo").unwrap(),
-            replacement: "+".to_string()
-        };
+        let regex = RegexCharacterFilter::new(RegexConfig::new("foo", "+")).unwrap();
         let a = base.wrap_layer(regex);
-        let c = a.wrap_layer(BoxCharacterFilter(Box::new(RegexCharacterFilter{
-            pattern: Regex::new(r"bar").unwrap(),
-            replacement: "-".to_string()
-        })));
-
-        let dynm: BoxCharFilterLayer = c.wrap_dynamic_layer(RegexCharacterFilter{
-            pattern: Regex::new(r"r").unwrap(),
-            replacement: "0".to_string()
-        });
+        let c = a.wrap_layer(BoxCharacterFilter(Box::new(RegexCharacterFilter::new(RegexConfig::new("bar", "-")).unwrap())));
+
+        let dynm: BoxCharFilterLayer = c.wrap_dynamic_layer(RegexCharacterFilter::new(RegexConfig::new("r", "0")).unwrap());
 
         let result = serde_json::to_string(&dynm).unwrap();
         println!("{:#}", result);
@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashSet};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::char_filter::CharacterFilterRegistry;
+use crate::char_filter::CharacterFilter;
+use crate::char_filter::character_filter_layer::{BoxCharFilterLayer, CharacterFilterLayers};
+use crate::char_filter::offset_mapping::OffsetMapping;
+use crate::language::Language;
+use crate::script::{script_set, Script};
+
+/// Applies the wrapped filters only to language segments of the given
+/// languages and scripts, e.g. to strip Greek accents without touching French.
+///
+/// Before language detection the segment is unknown, so the text passes
+/// through untouched: use it among the segment character filters.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConditionalCharFilter {
+    /// Languages of the filtered segments, any language when empty.
+    #[serde(default)]
+    pub languages: BTreeSet<Language>,
+    /// Scripts of the filtered segments, any script when empty.
+    #[serde(default, with = "script_set")]
+    #[schemars(with = "BTreeSet<String>")]
+    pub scripts: HashSet<Script>,
+    pub filters: BoxCharFilterLayer,
+}
+
+impl ConditionalCharFilter {
+    pub fn matches(&self, language: Language, script: Script) -> bool {
+        (self.languages.is_empty() || self.languages.contains(&language))
+            && (self.scripts.is_empty() || self.scripts.contains(&script))
+    }
+}
+
+#[typetag::serde]
+impl CharacterFilter for ConditionalCharFilter {
+    fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        text
+    }
+
+    fn apply_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        if !self.matches(language, script) {
+            return text;
+        }
+
+        self.filters.apply_layer_to_segment(text, language, script, offsets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::char_filter::character_filter_layer::BaseLevel;
+    use crate::char_filter::mapping_character_filter::{MappingCharFilter, MappingConfig};
+    use super::*;
+
+    #[test]
+    fn conditional() {
+        let mapping = MappingCharFilter::new(MappingConfig {
+            mappings: [("ά", "α"), ("έ", "ε")].into_iter().map(|(a, b)| (a.to_string(), b.to_string())).collect(),
+            mapping_files: vec![],
+        }).unwrap();
+        let filter = ConditionalCharFilter {
+            languages: BTreeSet::from([Language::Ell]),
+            scripts: HashSet::new(),
+            filters: BaseLevel.wrap_dynamic_layer(mapping),
+        };
+        let apply = |text: &'static str, language| filter.apply_to_segment(Cow::Borrowed(text), language, Script::Greek, &mut OffsetMapping::new());
+
+        assert_eq!(apply("καλά νέα", Language::Ell), "καλα νεα");
+        assert_eq!(apply("καλά νέα", Language::Fra), "καλά νέα");
+        assert_eq!(filter.apply(Cow::Borrowed("καλά")), "καλά");
+
+        let serialized = serde_json::to_string(&filter).unwrap();
+        let filter: ConditionalCharFilter = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(filter.apply_to_segment(Cow::Borrowed("έ"), Language::Ell, Script::Greek, &mut OffsetMapping::new()), "ε");
+    }
+}
@@ -0,0 +1,285 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::char_filter::CharacterFilterRegistry;
+use crate::char_filter::CharacterFilter;
+use crate::char_filter::offset_mapping::{OffsetMapping, ReplacementWriter};
+
+/// Strips HTML and XML markup: removes tags, comments, `<script>` and `<style>`
+/// contents, decodes character entities and turns block-level elements into
+/// line breaks so that words don't merge.
+#[derive(Clone, Default, JsonSchema, Serialize, Deserialize)]
+pub struct HtmlStripCharFilter {
+    /// Tags kept in the text as they are, e.g. `b` or `em`.
+    #[serde(default)]
+    pub escaped_tags: BTreeSet<String>,
+}
+
+#[typetag::serde]
+impl CharacterFilter for HtmlStripCharFilter {
+    fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        self.apply_with_offsets(text, &mut OffsetMapping::new())
+    }
+
+    fn apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        if !text.contains(['<', '&']) {
+            return text;
+        }
+
+        Cow::Owned(self.strip(&text, offsets))
+    }
+}
+
+impl HtmlStripCharFilter {
+    fn strip(&self, html: &str, offsets: &mut OffsetMapping) -> String {
+        let mut output = ReplacementWriter::new(html, offsets);
+        let mut index = 0;
+        while let Some(found) = html[index..].find(['<', '&']) {
+            let start = index + found;
+            let rest = &html[start..];
+            index = start + 1;
+
+            if rest.starts_with('&') {
+                if let Some((length, c)) = entity(rest) {
+                    output.replace(start..start + length, c.encode_utf8(&mut [0; 4]));
+                    index = start + length;
+                }
+            } else if rest.starts_with("<!--") {
+                let end = rest.find("-->").map_or(html.len(), |end| start + end + "-->".len());
+                output.replace(start..end, "");
+                index = end;
+            } else if rest.starts_with("<![CDATA[") {
+                // the content is kept as is, without decoding entities.
+                let content = start + "<![CDATA[".len();
+                let content_end = html[content..].find("]]>").map_or(html.len(), |end| content + end);
+                output.replace(start..content, "");
+                output.replace(content_end..(content_end + "]]>".len()).min(html.len()), "");
+                index = (content_end + "]]>".len()).min(html.len());
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                let end = rest.find('>').map_or(html.len(), |end| start + end + 1);
+                output.replace(start..end, "");
+                index = end;
+            } else if let Some(tag) = Tag::parse(rest) {
+                let end = start + tag.length;
+                index = end;
+                if self.escaped_tags.contains(&tag.name) {
+                    continue;
+                }
+                if !tag.closing && !tag.self_closing && matches!(tag.name.as_str(), "script" | "style") {
+                    let content_end = find_closing_tag(&html[end..], &tag.name).map_or(html.len(), |content_end| end + content_end);
+                    let closing_end = Tag::parse(&html[content_end..]).map_or(content_end, |closing| content_end + closing.length);
+                    output.replace(start..closing_end, "\n");
+                    index = closing_end;
+                } else {
+                    output.replace(start..end, if is_block(&tag.name) { "\n" } else { "" });
+                }
+            }
+        }
+
+        output.finish()
+    }
+}
+
+struct Tag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    // length of the tag in bytes, including the angle brackets.
+    length: usize,
+}
+
+impl Tag {
+    fn parse(text: &str) -> Option<Tag> {
+        let closing = text.starts_with("</");
+        let name_start = if closing { 2 } else { 1 };
+        let name_length = text[name_start..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == ':' || c == '_'))
+            .unwrap_or(text.len() - name_start);
+        let name = &text[name_start..name_start + name_length];
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+
+        // the end of the tag, skipping quoted attribute values.
+        let mut quote = None;
+        for (index, c) in text.char_indices().skip(name_start + name_length) {
+            match (quote, c) {
+                (Some(open), c) if c == open => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '<') => return None,
+                (None, '>') => {
+                    return Some(Tag {
+                        name: name.to_ascii_lowercase(),
+                        closing,
+                        self_closing: text[..index].ends_with('/'),
+                        length: index + 1,
+                    });
+                }
+                (None, _) => {}
+            }
+        }
+
+        None
+    }
+}
+
+// start of the closing tag of a `script` or `style` element.
+fn find_closing_tag(text: &str, name: &str) -> Option<usize> {
+    text.match_indices("</")
+        .map(|(index, _)| index)
+        .find(|&index| text.as_bytes()[index + 2..].get(..name.len()).is_some_and(|tag| tag.eq_ignore_ascii_case(name.as_bytes())))
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(name,
+        "address" | "article" | "aside" | "blockquote" | "body" | "br" | "caption" | "dd" | "details" | "dialog"
+        | "div" | "dl" | "dt" | "fieldset" | "figcaption" | "figure" | "footer" | "form" | "h1" | "h2" | "h3"
+        | "h4" | "h5" | "h6" | "head" | "header" | "hr" | "html" | "li" | "main" | "nav" | "ol" | "option" | "p"
+        | "pre" | "section" | "summary" | "table" | "tbody" | "td" | "tfoot" | "th" | "thead" | "title" | "tr" | "ul"
+    )
+}
+
+// decodes a character reference, returning its length and the character.
+fn entity(text: &str) -> Option<(usize, char)> {
+    let end = text[1..].find(';')? + 1;
+    let reference = &text[1..end];
+    let c = if let Some(number) = reference.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) => u32::from_str_radix(hex, 16).ok(),
+            None if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) => number.parse().ok(),
+            _ => return None,
+        };
+        code.and_then(char::from_u32).filter(|&c| c != '\0').unwrap_or(char::REPLACEMENT_CHARACTER)
+    } else {
+        named_entity(reference)?
+    };
+
+    Some((end + 1, c))
+}
+
+// Latin-1 entities, `&nbsp;` to `&yuml;`, are the code points 160 to 255.
+const LATIN_1: [&str; 96] = [
+    "nbsp", "iexcl", "cent", "pound", "curren", "yen", "brvbar", "sect", "uml", "copy", "ordf", "laquo", "not",
+    "shy", "reg", "macr", "deg", "plusmn", "sup2", "sup3", "acute", "micro", "para", "middot", "cedil", "sup1",
+    "ordm", "raquo", "frac14", "frac12", "frac34", "iquest", "Agrave", "Aacute", "Acirc", "Atilde", "Auml",
+    "Aring", "AElig", "Ccedil", "Egrave", "Eacute", "Ecirc", "Euml", "Igrave", "Iacute", "Icirc", "Iuml", "ETH",
+    "Ntilde", "Ograve", "Oacute", "Ocirc", "Otilde", "Ouml", "times", "Oslash", "Ugrave", "Uacute", "Ucirc",
+    "Uuml", "Yacute", "THORN", "szlig", "agrave", "aacute", "acirc", "atilde", "auml", "aring", "aelig", "ccedil",
+    "egrave", "eacute", "ecirc", "euml", "igrave", "iacute", "icirc", "iuml", "eth", "ntilde", "ograve", "oacute",
+    "ocirc", "otilde", "ouml", "divide", "oslash", "ugrave", "uacute", "ucirc", "uuml", "yacute", "thorn", "yuml",
+];
+
+fn named_entity(name: &str) -> Option<char> {
+    if let Some(index) = LATIN_1.iter().position(|&entity| entity == name) {
+        return char::from_u32(160 + index as u32);
+    }
+    let c = match name {
+        "quot" => '"',
+        "amp" => '&',
+        "apos" => '\'',
+        "lt" => '<',
+        "gt" => '>',
+        "OElig" => 'Œ',
+        "oelig" => 'œ',
+        "Scaron" => 'Š',
+        "scaron" => 'š',
+        "Yuml" => 'Ÿ',
+        "fnof" => 'ƒ',
+        "circ" => 'ˆ',
+        "tilde" => '˜',
+        "ensp" => '\u{2002}',
+        "emsp" => '\u{2003}',
+        "thinsp" => '\u{2009}',
+        "zwnj" => '\u{200C}',
+        "zwj" => '\u{200D}',
+        "lrm" => '\u{200E}',
+        "rlm" => '\u{200F}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "sbquo" => '‚',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "bdquo" => '„',
+        "dagger" => '†',
+        "Dagger" => '‡',
+        "bull" => '•',
+        "hellip" => '…',
+        "permil" => '‰',
+        "prime" => '′',
+        "Prime" => '″',
+        "lsaquo" => '‹',
+        "rsaquo" => '›',
+        "euro" => '€',
+        "trade" => '™',
+        "larr" => '←',
+        "uarr" => '↑',
+        "rarr" => '→',
+        "darr" => '↓',
+        "harr" => '↔',
+        "minus" => '−',
+        "ne" => '≠',
+        "le" => '≤',
+        "ge" => '≥',
+        "infin" => '∞',
+        _ => return None,
+    };
+
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::Analyzer;
+    use crate::analyzer::text_analyzer::TextAnalyzer;
+    use crate::char_filter::character_filter_layer::{BaseLevel, CharacterFilterLayers};
+    use crate::language_detection::whichlang::WhichLangDetector;
+    use crate::token_filter::token_filter_layer;
+    use crate::tokenizer::token_stream::TokenStream;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn html_strip() {
+        let filter = HtmlStripCharFilter::default();
+        let strip = |text: &'static str| filter.apply(Cow::Borrowed(text)).into_owned();
+
+        assert_eq!(strip("a<br>b<span class=\"x>y\">c</span>"), "a\nbc");
+        assert_eq!(strip("x &lt;y&gt; &#65;&#x42; caf&eacute;&nbsp;&unknown; a < b &"), "x <y> AB café\u{A0}&unknown; a < b &");
+        assert_eq!(strip("<script type=\"text/javascript\">if (a < b) {}</SCRIPT>ok<style>p{}</style>"), "\nok\n");
+        assert_eq!(strip("<!DOCTYPE html><?xml version=\"1.0\"?><!-- <p>no</p> --><![CDATA[a &amp; b]]>"), "a &amp; b");
+
+        let filter = HtmlStripCharFilter { escaped_tags: BTreeSet::from(["b".to_string()]) };
+        assert_eq!(filter.apply(Cow::Borrowed("<p><b>bold</b></p>")), "\n<b>bold</b>\n");
+    }
+
+    #[test]
+    fn offsets() {
+        let analyzer = TextAnalyzer {
+            character_filters: BaseLevel.wrap_layer(HtmlStripCharFilter::default()),
+            language_detector: WhichLangDetector::default(),
+            segment_character_filters: None,
+            tokenizer: WhitespaceTokenizer {},
+            token_filters: token_filter_layer::BaseLevel,
+        };
+        let html = "<html><head><title>Doc</title><style>p { color: red }</style></head>\
+            <body><p>Caf&eacute; &amp; <b>bar</b>s</p><!-- hidden --><div>next<br/>line</div>\
+            <script>var x = \"<p>\";</script></body></html>";
+
+        let tokens: Vec<(String, &str)> = analyzer.analyze(html)
+            .as_iter()
+            .map(|token| (token.text, &html[token.offset_from..token.offset_to]))
+            .collect();
+        assert_eq!(tokens, [
+            ("Doc".to_string(), "Doc"),
+            ("Café".to_string(), "Caf&eacute;"),
+            ("&".to_string(), "&amp;"),
+            ("bars".to_string(), "bar</b>s"),
+            ("next".to_string(), "next"),
+            ("line".to_string(), "line"),
+        ]);
+    }
+}
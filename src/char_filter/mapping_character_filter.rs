@@ -0,0 +1,197 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use aho_corasick::{AhoCorasick, MatchKind};
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
+use schemars::schema::Schema;
+use serde_derive::{Deserialize, Serialize};
+use crate::char_filter::CharacterFilterRegistry;
+use crate::char_filter::CharacterFilter;
+use crate::char_filter::offset_mapping::OffsetMapping;
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MappingConfig {
+    /// Literal replacements, taking precedence over the files.
+    #[serde(default)]
+    pub mappings: BTreeMap<String, String>,
+    /// Files with one `"source" => "replacement"` mapping per line, with `\uXXXX`
+    /// escapes and `#` comments, as in Lucene mapping files.
+    #[serde(default)]
+    pub mapping_files: Vec<PathBuf>,
+}
+
+/// Applies a table of literal replacements in a single pass, replacing the
+/// longest of the mappings matching at the leftmost position, e.g. `ß` with `ss`
+/// or `ﬁ` with `fi`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "MappingConfig", into = "MappingConfig")]
+pub struct MappingCharFilter {
+    config: MappingConfig,
+    automaton: Arc<AhoCorasick>,
+    replacements: Arc<Vec<String>>,
+}
+
+impl MappingCharFilter {
+    pub fn new(config: MappingConfig) -> io::Result<Self> {
+        let mut mappings = BTreeMap::new();
+        for path in &config.mapping_files {
+            let content = fs::read_to_string(path)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+            parse_mappings(&content, &mut mappings)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+        }
+        mappings.extend(config.mappings.iter().map(|(source, replacement)| (source.clone(), replacement.clone())));
+        if mappings.contains_key("") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "mapping of an empty string"));
+        }
+
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(mappings.keys())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        Ok(MappingCharFilter {
+            config,
+            automaton: Arc::new(automaton),
+            replacements: Arc::new(mappings.into_values().collect()),
+        })
+    }
+}
+
+impl TryFrom<MappingConfig> for MappingCharFilter {
+    type Error = io::Error;
+
+    fn try_from(config: MappingConfig) -> Result<Self, Self::Error> {
+        MappingCharFilter::new(config)
+    }
+}
+
+impl From<MappingCharFilter> for MappingConfig {
+    fn from(filter: MappingCharFilter) -> Self {
+        filter.config
+    }
+}
+
+impl JsonSchema for MappingCharFilter {
+    fn schema_name() -> String {
+        "MappingCharFilter".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        MappingConfig::json_schema(gen)
+    }
+}
+
+// reads `"source" => "replacement"` lines.
+fn parse_mappings(content: &str, mappings: &mut BTreeMap<String, String>) -> io::Result<()> {
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("mapping at line {}: {}", number + 1, message));
+        let (source, rest) = quoted(line).ok_or_else(|| error("expected a quoted source"))?;
+        let rest = rest.trim_start().strip_prefix("=>").ok_or_else(|| error("expected `=>`"))?;
+        let (replacement, rest) = quoted(rest.trim_start()).ok_or_else(|| error("expected a quoted replacement"))?;
+        if !rest.trim().is_empty() && !rest.trim().starts_with('#') {
+            return Err(error("unexpected text after the replacement"));
+        }
+        mappings.insert(source, replacement);
+    }
+
+    Ok(())
+}
+
+// unescapes a string in double quotes at the start of the text, returning it and the rest of the text.
+fn quoted(text: &str) -> Option<(String, &str)> {
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    let mut result = String::new();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((result, &text[index + 2..])),
+            '\\' => match chars.next()?.1 {
+                'n' => result.push('\n'),
+                't' => result.push('\t'),
+                'r' => result.push('\r'),
+                'u' => {
+                    let hex: String = (0..4).map(|_| chars.next().map(|(_, c)| c)).collect::<Option<_>>()?;
+                    result.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                }
+                c => result.push(c),
+            },
+            c => result.push(c),
+        }
+    }
+
+    None
+}
+
+#[typetag::serde]
+impl CharacterFilter for MappingCharFilter {
+    fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        self.apply_with_offsets(text, &mut OffsetMapping::new())
+    }
+
+    fn apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        let mut matches = self.automaton.find_iter(text.as_ref()).peekable();
+        if matches.peek().is_none() {
+            return text;
+        }
+
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for matched in matches {
+            result.push_str(&text[last..matched.start()]);
+            let start = result.len();
+            result.push_str(&self.replacements[matched.pattern().as_usize()]);
+            offsets.replace(start..result.len(), matched.range());
+            last = matched.end();
+        }
+        result.push_str(&text[last..]);
+
+        Cow::Owned(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping_file() -> PathBuf {
+        PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mapping/mapping.txt"))
+    }
+
+    #[test]
+    fn mapping() {
+        let filter = MappingCharFilter::new(MappingConfig {
+            mappings: BTreeMap::from([("&&".to_string(), " or ".to_string()), ("ß".to_string(), "sz".to_string())]),
+            mapping_files: vec![mapping_file()],
+        }).unwrap();
+
+        assert_eq!(filter.apply(Cow::Borrowed("ﬁne ﬂow: Straße & X && Y")), "fine flow: Strasze  and  X  or  Y");
+        assert!(matches!(filter.apply(Cow::Borrowed("nothing to map")), Cow::Borrowed(_)));
+
+        let mut offsets = OffsetMapping::new();
+        let text = "Straße & co";
+        let result = filter.apply_with_offsets(Cow::Borrowed(text), &mut offsets);
+        let and = result.find("and").unwrap();
+        assert_eq!(&text[offsets.correct_start(and)..offsets.correct_end(and + 3)], "&");
+        assert_eq!(&text[offsets.correct_start(result.len() - 2)..], "co");
+    }
+
+    #[test]
+    fn invalid_mapping() {
+        let path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mapping/invalid.txt"));
+        let err = MappingCharFilter::new(MappingConfig { mappings: BTreeMap::new(), mapping_files: vec![path] }).err().unwrap();
+        assert!(err.to_string().contains("line 2"));
+
+        let serialized = r#"{"mappings":{"ae":"æ"}}"#;
+        let filter: MappingCharFilter = serde_json::from_str(serialized).unwrap();
+        assert_eq!(filter.apply(Cow::Borrowed("aether")), "æther");
+        assert_eq!(serde_json::to_string(&filter).unwrap(), r#"{"mappings":{"ae":"æ"},"mapping_files":[]}"#);
+    }
+}
@@ -0,0 +1,446 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::char_filter::CharacterFilterRegistry;
+use crate::char_filter::CharacterFilter;
+use crate::char_filter::offset_mapping::{OffsetMapping, ReplacementWriter};
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum CodeBlocks {
+    /// Removes fenced code blocks.
+    #[default]
+    Drop,
+    /// Keeps the content of fenced code blocks as it is.
+    Keep,
+}
+
+/// Strips Markdown and wiki markup: keeps the text of links and images, removes
+/// emphasis, heading, quote, list and table markers and fenced code blocks.
+/// Removed lines keep their line breaks so that words don't merge.
+#[derive(Clone, Default, JsonSchema, Serialize, Deserialize)]
+pub struct MarkdownStripCharFilter {
+    /// Keeps link and image targets after their text.
+    #[serde(default)]
+    pub keep_link_targets: bool,
+    #[serde(default)]
+    pub code_blocks: CodeBlocks,
+}
+
+#[typetag::serde]
+impl CharacterFilter for MarkdownStripCharFilter {
+    fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        self.apply_with_offsets(text, &mut OffsetMapping::new())
+    }
+
+    fn apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        if !may_contain_markup(&text) {
+            return text;
+        }
+        // the replacements are kept only along with the changed text.
+        let mut replacements = OffsetMapping::new();
+        let result = self.strip(&text, &mut replacements);
+        if result == text {
+            return text;
+        }
+        offsets.append(replacements);
+
+        Cow::Owned(result)
+    }
+}
+
+impl MarkdownStripCharFilter {
+    fn strip(&self, text: &str, offsets: &mut OffsetMapping) -> String {
+        let mut output = ReplacementWriter::new(text, offsets);
+        // marker and length of the opening fence of the current code block.
+        let mut fence = None;
+        let mut start = 0;
+        for line in text.split_inclusive('\n') {
+            let end = start + line.trim_end_matches(['\n', '\r']).len();
+            self.strip_line(text, start..end, &mut fence, &mut output);
+            start += line.len();
+        }
+
+        output.finish()
+    }
+
+    fn strip_line(&self, text: &str, line: Range<usize>, fence: &mut Option<(char, usize)>, output: &mut ReplacementWriter) {
+        let content = text[line.clone()].trim_start_matches([' ', '\t']);
+        let mut position = line.end - content.len();
+
+        if let Some((marker, length)) = *fence {
+            let closing = content.trim_end().trim_start_matches(marker);
+            if closing.is_empty() && content.trim_end().len() >= length {
+                *fence = None;
+                output.replace(line, "");
+            } else if self.code_blocks == CodeBlocks::Drop {
+                output.replace(line, "");
+            }
+            return;
+        }
+        for marker in ['`', '~'] {
+            let length = content.len() - content.trim_start_matches(marker).len();
+            if length >= 3 {
+                *fence = Some((marker, length));
+                output.replace(line, "");
+                return;
+            }
+        }
+        if is_break(content) {
+            output.replace(line, "");
+            return;
+        }
+
+        // block quote and list markers, possibly nested.
+        loop {
+            let rest = &text[position..line.end];
+            let Some(length) = container_marker(rest) else {
+                break;
+            };
+            let spaces = rest[length..].len() - rest[length..].trim_start_matches([' ', '\t']).len();
+            output.replace(position..position + length, "");
+            position += length + spaces;
+        }
+
+        let rest = &text[position..line.end];
+        let mut end = line.end;
+        let mut closing = None;
+        let hashes = rest.len() - rest.trim_start_matches('#').len();
+        let equals = rest.len() - rest.trim_start_matches('=').len();
+        if (1..=6).contains(&hashes) && (rest.len() == hashes || rest[hashes..].starts_with([' ', '\t'])) {
+            output.replace(position..position + hashes, "");
+            position += hashes;
+            let trimmed = text[position..line.end].trim_end();
+            let heading = trimmed.trim_end_matches('#');
+            if heading.len() < trimmed.len() && heading.ends_with([' ', '\t']) {
+                end = position + heading.len();
+                closing = Some(end..position + trimmed.len());
+            }
+        } else if equals > 0 {
+            // `== Heading ==` in wiki markup.
+            let trimmed = rest.trim_end();
+            let heading = trimmed.trim_end_matches('=');
+            if trimmed.len() - heading.len() == equals && heading.len() > equals {
+                output.replace(position..position + equals, "");
+                position += equals;
+                end = position + heading.len() - equals;
+                closing = Some(end..end + equals);
+            }
+        } else if rest.starts_with('|') && rest.contains('-') && rest.trim_end().chars().all(|c| matches!(c, '|' | ':' | '-' | ' ' | '\t')) {
+            // the delimiter row of a table.
+            output.replace(position..line.end, "");
+            return;
+        } else if let Some((label, destination)) = reference_definition(rest) {
+            if label.starts_with('^') || self.keep_link_targets {
+                let length = rest.len() - destination.len();
+                output.replace(position..position + length, "");
+                position += length;
+            } else {
+                output.replace(position..line.end, "");
+                return;
+            }
+        }
+
+        self.strip_inline(text, position..end, rest.starts_with('|'), output);
+        if let Some(closing) = closing {
+            output.replace(closing, "");
+        }
+    }
+
+    fn strip_inline(&self, text: &str, range: Range<usize>, table: bool, output: &mut ReplacementWriter) {
+        // closing markup of the links being stripped, innermost last, and its replacement.
+        let mut closings: Vec<(Range<usize>, String)> = Vec::new();
+        let mut index = range.start;
+        while index < range.end {
+            let limit = closings.last().map_or(range.end, |(closing, _)| closing.start);
+            if index >= limit {
+                let (closing, replacement) = closings.pop().unwrap();
+                output.replace(closing.clone(), &replacement);
+                index = closing.end;
+                continue;
+            }
+            let rest = &text[index..limit];
+            let c = rest.chars().next().unwrap();
+            let run = rest.len() - rest.trim_start_matches(c).len();
+            match c {
+                '\\' if rest[1..].starts_with(|c: char| c.is_ascii_punctuation()) => {
+                    output.replace(index..index + 1, "");
+                    index += 2;
+                }
+                '`' => {
+                    // a code span ends with a run of backticks of the same length.
+                    let closing = (run..rest.len())
+                        .find(|&start| rest.is_char_boundary(start)
+                            && rest[start..].starts_with(&rest[..run])
+                            && !rest[..start].ends_with('`')
+                            && !rest[start + run..].starts_with('`'));
+                    if let Some(start) = closing {
+                        output.replace(index..index + run, "");
+                        output.replace(index + start..index + start + run, "");
+                        index += start + run;
+                    } else {
+                        index += run;
+                    }
+                }
+                '!' if rest[1..].starts_with('[') => {
+                    if let Some(closing) = self.link(text, index + 1, limit) {
+                        output.replace(index..index + 2, "");
+                        closings.push(closing);
+                    }
+                    index += 2;
+                }
+                '[' => {
+                    if let Some((label, closing)) = wiki_link(text, index, limit) {
+                        let opening = if rest.starts_with("[[") { 2 } else { 1 };
+                        if self.keep_link_targets && label > index + opening {
+                            output.replace(index..index + opening, "");
+                            output.replace(label - 1..label, " ");
+                        } else {
+                            output.replace(index..label, "");
+                        }
+                        closings.push((closing, String::new()));
+                        index = label;
+                    } else {
+                        if let Some(closing) = self.link(text, index, limit) {
+                            output.replace(index..index + 1, "");
+                            closings.push(closing);
+                        }
+                        index += 1;
+                    }
+                }
+                '<' => {
+                    // autolinks keep their address, which is also their text.
+                    let end = rest.find('>').filter(|&end| {
+                        let address = &rest[1..end];
+                        !address.contains(char::is_whitespace) && (address.contains("://") || address.contains('@'))
+                    });
+                    if let Some(end) = end {
+                        output.replace(index..index + 1, "");
+                        output.replace(index + end..index + end + 1, "");
+                        index += end + 1;
+                    } else {
+                        index += 1;
+                    }
+                }
+                '*' | '_' | '~' | '\'' => {
+                    let before = text[..index].chars().next_back();
+                    let after = text[index + run..range.end].chars().next();
+                    let flanking = before.is_some_and(|c| !c.is_whitespace()) || after.is_some_and(|c| !c.is_whitespace());
+                    let intraword = before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric);
+                    let delimiter = match c {
+                        '~' => run == 2,
+                        '\'' => (2..=5).contains(&run),
+                        _ => true,
+                    };
+                    if delimiter && flanking && !intraword {
+                        output.replace(index..index + run, "");
+                    }
+                    index += run;
+                }
+                '|' if table => {
+                    output.replace(index..index + 1, " ");
+                    index += 1;
+                }
+                c => index += c.len_utf8(),
+            }
+        }
+    }
+
+    // the closing markup of an inline or reference link starting at `start`, and its replacement.
+    fn link(&self, text: &str, start: usize, limit: usize) -> Option<(Range<usize>, String)> {
+        let close = matching(text, start, limit)?;
+        let rest = &text[close + 1..limit];
+        if rest.starts_with('(') {
+            let end = matching(text, close + 1, limit)?;
+            let destination = text[close + 2..end].trim();
+            let target = match destination.strip_prefix('<') {
+                Some(destination) => destination.split('>').next().unwrap_or_default(),
+                None => destination.split_whitespace().next().unwrap_or_default(),
+            };
+            let replacement = if self.keep_link_targets && !target.is_empty() { format!(" {}", target) } else { String::new() };
+            Some((close..end + 1, replacement))
+        } else if rest.starts_with('[') {
+            let end = matching(text, close + 1, limit)?;
+            Some((close..end + 1, String::new()))
+        } else {
+            None
+        }
+    }
+}
+
+// whether the text has any of the characters markup starts with, or a line starting with an ordered list marker.
+fn may_contain_markup(text: &str) -> bool {
+    text.contains(['#', '*', '_', '~', '`', '[', '!', '<', '>', '|', '\\', '=', '-', '+'])
+        || text.contains("''")
+        || text.lines().any(|line| container_marker(line.trim_start_matches([' ', '\t'])).is_some())
+}
+
+// whether the line is a thematic break or the underline of a heading.
+fn is_break(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    match marks.first() {
+        Some('-' | '*' | '_') => marks.len() >= 3 && marks.iter().all(|&c| c == marks[0]),
+        Some('=') => !line.trim_end().contains(char::is_whitespace) && marks.iter().all(|&c| c == '='),
+        _ => false,
+    }
+}
+
+// length of a block quote, list item or task list marker at the start of the line.
+fn container_marker(line: &str) -> Option<usize> {
+    let followed_by_space = |length: usize| line[length..].starts_with([' ', '\t']).then_some(length);
+    if line.starts_with('>') {
+        return Some(1);
+    }
+    if line.starts_with(['-', '*', '+']) {
+        return followed_by_space(1);
+    }
+    if ["[ ]", "[x]", "[X]"].iter().any(|task| line.starts_with(task)) {
+        return followed_by_space(3);
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if (1..=9).contains(&digits) && line[digits..].starts_with(['.', ')']) {
+        return followed_by_space(digits + 1);
+    }
+
+    None
+}
+
+// the label and the rest of a `[label]: destination "title"` line.
+fn reference_definition(line: &str) -> Option<(&str, &str)> {
+    let (label, rest) = line.strip_prefix('[')?.split_once("]:")?;
+    if label.is_empty() || label.contains(['[', ']']) {
+        return None;
+    }
+
+    Some((label, rest.trim_start()))
+}
+
+// start of the label and the closing brackets of a `[[target|label]]` or `[https://target label]` link.
+fn wiki_link(text: &str, start: usize, limit: usize) -> Option<(usize, Range<usize>)> {
+    let rest = &text[start..limit];
+    if let Some(link) = rest.strip_prefix("[[") {
+        let end = link.find("]]")?;
+        let label = link[..end].find('|').map_or(0, |separator| separator + 1);
+        return Some((start + 2 + label, start + 2 + end..start + 4 + end));
+    }
+    if rest[1..].starts_with("http://") || rest[1..].starts_with("https://") {
+        let end = rest.find(']')?;
+        let separator = rest[..end].find(' ')?;
+        return Some((start + separator + 1, start + end..start + end + 1));
+    }
+
+    None
+}
+
+// the bracket closing the one at `start`, skipping nested and escaped brackets.
+fn matching(text: &str, start: usize, limit: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let (open, close) = match bytes[start] {
+        b'(' => (b'(', b')'),
+        _ => (b'[', b']'),
+    };
+    let mut depth = 0;
+    let mut index = start;
+    while index < limit {
+        match bytes[index] {
+            b'\\' => index += 1,
+            b if b == open => depth += 1,
+            b if b == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::Analyzer;
+    use crate::analyzer::text_analyzer::TextAnalyzer;
+    use crate::char_filter::character_filter_layer::{BaseLevel, CharacterFilterLayers};
+    use crate::language_detection::whichlang::WhichLangDetector;
+    use crate::token_filter::token_filter_layer;
+    use crate::tokenizer::token_stream::TokenStream;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    const MARKDOWN: &str = "# Getting *started* #
+Read the [guide](https://example.com/guide \"Guide\") and ![a diagram](img/diagram.png)
+with **bold**, __strong__, ~~old~~ and `inline code`, not snake_case or 2 * 3.
+
+> - [x] quoted *item*
+1. first <https://example.com>
+
+```rust
+let x = 1;
+```
+| Name | Value |
+|------|:-----:|
+| a\\*b | [ref link][1] |
+
+---
+== Wiki heading ==
+See [[Main Page|the main page]], [[Help]] and [https://example.org the site]. '''Bold''' text.
+[1]: https://example.com/ref";
+
+    #[test]
+    fn markdown_strip() {
+        let filter = MarkdownStripCharFilter::default();
+        assert_eq!(filter.apply(Cow::Borrowed(MARKDOWN)), " Getting started \n\
+            Read the guide and a diagram\n\
+            with bold, strong, old and inline code, not snake_case or 2 * 3.\n\n   \
+            quoted item\n \
+            first https://example.com\n\n\n\n\n  \
+            Name   Value  \n\n  \
+            a*b   ref link  \n\n\n \
+            Wiki heading \n\
+            See the main page, Help and the site. Bold text.\n");
+        assert_eq!(filter.apply(Cow::Borrowed("`é` and `ü")), "é and `ü");
+        assert!(matches!(filter.apply(Cow::Borrowed("plain text, 2 * 3 = 6")), Cow::Borrowed(_)));
+        assert_eq!(filter.apply(Cow::Borrowed("[^a *b*]: note")), "note");
+        assert_eq!(filter.apply(Cow::Borrowed("intro\n 1. first")), "intro\n  first");
+
+        // nothing to strip records no replacements.
+        let mut offsets = OffsetMapping::new();
+        assert!(matches!(filter.apply_with_offsets(Cow::Borrowed("plain text, 2 * 3 = 6"), &mut offsets), Cow::Borrowed(_)));
+        assert!(matches!(filter.apply_with_offsets(Cow::Borrowed("plain text"), &mut offsets), Cow::Borrowed(_)));
+        assert!(offsets.is_empty());
+
+        let filter = MarkdownStripCharFilter { keep_link_targets: true, code_blocks: CodeBlocks::Keep };
+        let result = filter.apply(Cow::Borrowed(MARKDOWN));
+        assert!(result.contains("Read the guide https://example.com/guide and a diagram img/diagram.png\n"));
+        assert!(result.contains("\nlet x = 1;\n"));
+        assert!(result.contains("See Main Page the main page, Help and https://example.org the site."));
+        assert!(result.ends_with("\nhttps://example.com/ref"));
+        assert_eq!(filter.apply(Cow::Borrowed("[a *b*]: http://x")), "http://x");
+    }
+
+    #[test]
+    fn offsets() {
+        let analyzer = TextAnalyzer {
+            character_filters: BaseLevel.wrap_layer(MarkdownStripCharFilter::default()),
+            language_detector: WhichLangDetector::default(),
+            segment_character_filters: None,
+            tokenizer: WhitespaceTokenizer {},
+            token_filters: token_filter_layer::BaseLevel,
+        };
+        let markdown = "## A **bold** [link](http://x.y)\n```\ncode\n```\n- `end`";
+
+        let tokens: Vec<(String, &str)> = analyzer.analyze(markdown)
+            .as_iter()
+            .map(|token| (token.text, &markdown[token.offset_from..token.offset_to]))
+            .collect();
+        assert_eq!(tokens, [
+            ("A".to_string(), "A"),
+            ("bold".to_string(), "bold"),
+            ("link".to_string(), "link"),
+            ("end".to_string(), "end"),
+        ]);
+    }
+}
@@ -1,5 +1,11 @@
 pub mod character_filter_layer;
 pub mod regex_character_filter;
+pub mod offset_mapping;
+pub mod html_strip_character_filter;
+pub mod mapping_character_filter;
+pub mod unicode_normalize_character_filter;
+pub mod markdown_strip_character_filter;
+pub mod conditional_character_filter;
 
 use std::borrow::Cow;
 use std::ops::CoerceUnsized;
@@ -8,12 +14,33 @@ use schemars::JsonSchema;
 use schemars::schema::Schema;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use typetag::__private::erased_serde;
+use crate::char_filter::offset_mapping::OffsetMapping;
+use crate::language::Language;
+use crate::script::Script;
 
 
 pub struct BoxCharacterFilter(Box<dyn BoxableCharacterFilter>);
 #[typetag::serde(receiver = BoxableCharacterFilter)]
 pub trait CharacterFilter: 'static + Send + Sync + Clone{
     fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a,str>;
+
+    /// Applies the filter, recording the replacements it made in `offsets`.
+    /// By default everything between the common prefix and suffix of the input and the output is one replacement.
+    fn apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        // the filter borrows the input so that it is kept to compare with, and left as it is when unchanged.
+        let output = match self.apply(Cow::Borrowed(&text)) {
+            Cow::Borrowed(_) => return text,
+            Cow::Owned(output) => output,
+        };
+        offsets.replace_difference(&text, &output);
+        Cow::Owned(output)
+    }
+
+    /// Applies the filter to a language segment of the text, after language detection.
+    /// By default the language and the script of the segment are ignored.
+    fn apply_to_segment<'a>(&self, text: Cow<'a, str>, _language: Language, _script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        self.apply_with_offsets(text, offsets)
+    }
 }
 
 
@@ -21,6 +48,8 @@ pub trait BoxableCharacterFilter: 'static + Send + Sync + typetag::Serialize{
     /// Clone this tokenizer.
     fn box_clone(&self) -> Box<dyn BoxableCharacterFilter>;
     fn box_apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str>;
+    fn box_apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str>;
+    fn box_apply_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str>;
     fn type_name<'a>(&self) -> &'static str;
 }
 
@@ -39,6 +68,14 @@ impl<T: CharacterFilter + Serialize> BoxableCharacterFilter for T{
         self.apply(text)
     }
 
+    fn box_apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        self.apply_with_offsets(text, offsets)
+    }
+
+    fn box_apply_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        self.apply_to_segment(text, language, script, offsets)
+    }
+
     fn type_name<'a>(&self) -> &'static str {
         CharacterFilter::type_name(self)
     }
@@ -49,6 +86,14 @@ impl CharacterFilter for BoxCharacterFilter {
         self.0.box_apply(text)
     }
 
+    fn apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        self.0.box_apply_with_offsets(text, offsets)
+    }
+
+    fn apply_to_segment<'a>(&self, text: Cow<'a, str>, language: Language, script: Script, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        self.0.box_apply_to_segment(text, language, script, offsets)
+    }
+
     fn type_name(&self) -> &'static str {
         &self.0.type_name()
     }
@@ -0,0 +1,157 @@
+use std::ops::Range;
+
+/// Maps byte offsets of a text changed by character filters back to the
+/// original text, so that tokens point to the raw input.
+#[derive(Clone, Debug, Default)]
+pub struct OffsetMapping {
+    // replacements made by each filter, ordered by their offsets in the output of the filter.
+    stages: Vec<Vec<Replacement>>,
+}
+
+#[derive(Clone, Debug)]
+struct Replacement {
+    output: Range<usize>,
+    input: Range<usize>,
+}
+
+impl OffsetMapping {
+    pub fn new() -> Self {
+        OffsetMapping::default()
+    }
+
+    /// Starts recording the replacements of the next filter applied to the text.
+    pub fn begin_stage(&mut self) {
+        if self.stages.last().is_none_or(|stage| !stage.is_empty()) {
+            self.stages.push(Vec::new());
+        }
+    }
+
+    /// Records that the `output` range of the filtered text replaced the `input` range of the filter input.
+    /// Replacements must be recorded in the order of the text.
+    pub fn replace(&mut self, output: Range<usize>, input: Range<usize>) {
+        if output.len() == input.len() {
+            return;
+        }
+        if self.stages.is_empty() {
+            self.stages.push(Vec::new());
+        }
+        self.stages.last_mut().unwrap().push(Replacement { output, input });
+    }
+
+    /// Records the difference between the input and the output of a filter as a
+    /// single replacement of everything between their common prefix and suffix.
+    pub fn replace_difference(&mut self, input: &str, output: &str) {
+        let prefix = input.bytes().zip(output.bytes()).take_while(|(a, b)| a == b).count();
+        if prefix == input.len() && prefix == output.len() {
+            return;
+        }
+        let suffix = input[prefix..].bytes().rev().zip(output[prefix..].bytes().rev()).take_while(|(a, b)| a == b).count();
+        // keep the ranges on character boundaries.
+        let prefix = (0..=prefix).rev().find(|&prefix| input.is_char_boundary(prefix) && output.is_char_boundary(prefix)).unwrap_or(0);
+        let suffix = (0..=suffix)
+            .rev()
+            .find(|&suffix| input.is_char_boundary(input.len() - suffix) && output.is_char_boundary(output.len() - suffix))
+            .unwrap_or(0);
+        self.replace(prefix..output.len() - suffix, prefix..input.len() - suffix);
+    }
+
+    /// Records the replacements of another mapping, made by the same filter.
+    pub fn append(&mut self, other: OffsetMapping) {
+        for replacement in other.stages.into_iter().flatten() {
+            self.replace(replacement.output, replacement.input);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.iter().all(Vec::is_empty)
+    }
+
+    /// Original offset of the start of a token.
+    pub fn correct_start(&self, offset: usize) -> usize {
+        self.stages.iter().rev().fold(offset, |offset, stage| {
+            let index = stage.partition_point(|replacement| replacement.output.start <= offset);
+            match index.checked_sub(1).map(|index| &stage[index]) {
+                // inside a replacement.
+                Some(replacement) if offset < replacement.output.end => replacement.input.start,
+                Some(replacement) => replacement.input.end + (offset - replacement.output.end),
+                None => offset,
+            }
+        })
+    }
+
+    /// Original offset of the end of a token.
+    pub fn correct_end(&self, offset: usize) -> usize {
+        self.stages.iter().rev().fold(offset, |offset, stage| {
+            let index = stage.partition_point(|replacement| replacement.output.start < offset);
+            match index.checked_sub(1).map(|index| &stage[index]) {
+                Some(replacement) if offset <= replacement.output.end => replacement.input.end,
+                Some(replacement) => replacement.input.end + (offset - replacement.output.end),
+                None => offset,
+            }
+        })
+    }
+}
+
+/// Builds the output of a filter from its input, recording the replacements made.
+pub struct ReplacementWriter<'a> {
+    input: &'a str,
+    output: String,
+    // end of the input copied to the output.
+    copied: usize,
+    offsets: &'a mut OffsetMapping,
+}
+
+impl<'a> ReplacementWriter<'a> {
+    pub fn new(input: &'a str, offsets: &'a mut OffsetMapping) -> Self {
+        ReplacementWriter {
+            input,
+            output: String::with_capacity(input.len()),
+            copied: 0,
+            offsets,
+        }
+    }
+
+    /// Copies the input up to `range` and replaces the range with `replacement`.
+    pub fn replace(&mut self, range: Range<usize>, replacement: &str) {
+        self.output.push_str(&self.input[self.copied..range.start]);
+        let start = self.output.len();
+        self.output.push_str(replacement);
+        self.offsets.replace(start..self.output.len(), range.clone());
+        self.copied = range.end;
+    }
+
+    /// Copies the rest of the input.
+    pub fn finish(mut self) -> String {
+        self.output.push_str(&self.input[self.copied..]);
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_mapping() {
+        // `<b>hi</b> &amp; x` => `hi & x`
+        let mut mapping = OffsetMapping::new();
+        mapping.begin_stage();
+        mapping.replace(0..0, 0..3);
+        mapping.replace(2..2, 5..9);
+        mapping.replace(3..4, 10..15);
+        assert_eq!((mapping.correct_start(0), mapping.correct_end(2)), (3, 5));
+        assert_eq!((mapping.correct_start(3), mapping.correct_end(4)), (10, 15));
+        assert_eq!((mapping.correct_start(5), mapping.correct_end(6)), (16, 17));
+
+        // `hi & x` => `hi and x`
+        mapping.begin_stage();
+        mapping.replace_difference("hi & x", "hi and x");
+        assert_eq!((mapping.correct_start(3), mapping.correct_end(6)), (10, 15));
+        assert_eq!((mapping.correct_start(7), mapping.correct_end(8)), (16, 17));
+
+        let mut unchanged = OffsetMapping::new();
+        unchanged.begin_stage();
+        unchanged.replace_difference("hi & x", "hi & x");
+        assert!(unchanged.is_empty());
+    }
+}
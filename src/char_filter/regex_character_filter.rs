@@ -1,28 +1,264 @@
 use std::borrow::Cow;
-use regex::Regex;
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+use schemars::gen::SchemaGenerator;
 use schemars::JsonSchema;
+use schemars::schema::Schema;
 use serde_derive::{Deserialize, Serialize};
 
 
 use crate::char_filter::CharacterFilterRegistry;
 
 use crate::char_filter::CharacterFilter;
-extern crate serde_regex;
-#[derive(Clone, JsonSchema, Serialize, Deserialize)]
-pub struct RegexCharacterFilter{
-    #[serde(with = "serde_regex")]
-    #[schemars(with = "std::string::String")]
-    pub pattern: Regex,
-    pub replacement: String
+use crate::char_filter::offset_mapping::OffsetMapping;
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RegexReplacement {
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RegexConfig {
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Replacement of `pattern`, with `$1` and `${name}` expanded to the captured groups.
+    #[serde(default)]
+    pub replacement: String,
+    /// More patterns replaced in the same pass. Of overlapping matches the
+    /// leftmost one is replaced, then the one of the first pattern listed.
+    #[serde(default)]
+    pub patterns: Vec<RegexReplacement>,
+    /// Inserts the replacements as they are, without expanding `$` references.
+    #[serde(default)]
+    pub literal: bool,
+    /// Maximum number of replacements in a text.
+    #[serde(default)]
+    pub max_replacements: Option<usize>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Makes `^` and `$` match at the start and end of lines.
+    #[serde(default)]
+    pub multi_line: bool,
+    /// Makes classes such as `\w` and case insensitivity Unicode-aware rather than ASCII only.
+    #[serde(default = "default_unicode")]
+    pub unicode: bool,
+    /// Maximum size in bytes of each compiled pattern, to reject pathological patterns.
+    #[serde(default = "default_size_limit")]
+    pub size_limit: usize,
+}
+
+fn default_unicode() -> bool {
+    true
+}
+
+fn default_size_limit() -> usize {
+    1 << 20
+}
+
+impl RegexConfig {
+    pub fn new(pattern: impl Into<String>, replacement: impl Into<String>) -> Self {
+        RegexConfig {
+            pattern: Some(pattern.into()),
+            replacement: replacement.into(),
+            patterns: Vec::new(),
+            literal: false,
+            max_replacements: None,
+            case_insensitive: false,
+            multi_line: false,
+            unicode: default_unicode(),
+            size_limit: default_size_limit(),
+        }
+    }
+
+    /// Replacement of the pattern at `index` in [`RegexConfig::replacements`].
+    fn replacement(&self, index: usize) -> &str {
+        match (&self.pattern, index) {
+            (Some(_), 0) => &self.replacement,
+            (Some(_), index) => &self.patterns[index - 1].replacement,
+            (None, index) => &self.patterns[index].replacement,
+        }
+    }
+
+    fn replacements(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pattern.iter()
+            .map(|pattern| (pattern.as_str(), self.replacement.as_str()))
+            .chain(self.patterns.iter().map(|pattern| (pattern.pattern.as_str(), pattern.replacement.as_str())))
+    }
+}
+
+/// Replaces the matches of regular expressions, e.g. `(\d+)-(\d+)` with `$1$2`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "RegexConfig", into = "RegexConfig")]
+pub struct RegexCharacterFilter {
+    config: RegexConfig,
+    // finds the patterns matching a text at all, to search only for those.
+    set: RegexSet,
+    patterns: Vec<Regex>,
+}
+
+impl RegexCharacterFilter {
+    pub fn new(config: RegexConfig) -> Result<Self, regex::Error> {
+        let set = RegexSetBuilder::new(config.replacements().map(|(pattern, _)| pattern))
+            .case_insensitive(config.case_insensitive)
+            .multi_line(config.multi_line)
+            .unicode(config.unicode)
+            .size_limit(config.size_limit)
+            .build()?;
+        let patterns = config.replacements()
+            .map(|(pattern, _)| RegexBuilder::new(pattern)
+                .case_insensitive(config.case_insensitive)
+                .multi_line(config.multi_line)
+                .unicode(config.unicode)
+                .size_limit(config.size_limit)
+                .build())
+            .collect::<Result<_, _>>()?;
+
+        Ok(RegexCharacterFilter { config, set, patterns })
+    }
+
+    /// Replaces the matches of a compiled pattern, as the filter did when its
+    /// fields were the pattern and the replacement. Only the flags written in
+    /// the pattern, e.g. `(?i)`, are kept, not those of a `RegexBuilder`.
+    pub fn from_regex(pattern: Regex, replacement: impl Into<String>) -> Result<Self, regex::Error> {
+        RegexCharacterFilter::new(RegexConfig::new(pattern.as_str(), replacement))
+    }
+}
+
+impl TryFrom<RegexConfig> for RegexCharacterFilter {
+    type Error = regex::Error;
+
+    fn try_from(config: RegexConfig) -> Result<Self, Self::Error> {
+        RegexCharacterFilter::new(config)
+    }
+}
+
+impl From<RegexCharacterFilter> for RegexConfig {
+    fn from(filter: RegexCharacterFilter) -> Self {
+        filter.config
+    }
+}
+
+impl JsonSchema for RegexCharacterFilter {
+    fn schema_name() -> String {
+        "RegexCharacterFilter".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        RegexConfig::json_schema(gen)
+    }
 }
 
 #[typetag::serde]
 impl CharacterFilter for RegexCharacterFilter {
     fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
-        let result = self.pattern.replace_all(&text, &self.replacement);
-        match result {
-            Cow::Borrowed(_) => text,
-            Cow::Owned(result) => Cow::Owned(result)
+        self.apply_with_offsets(text, &mut OffsetMapping::new())
+    }
+
+    fn apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        let input = text.as_ref();
+        // the next match of each pattern matching the text.
+        let mut matches: Vec<_> = self.set.matches(input)
+            .into_iter()
+            .map(|pattern| (pattern, self.patterns[pattern].find(input)))
+            .collect();
+
+        let mut result = String::new();
+        let mut last = 0;
+        let mut replaced = 0;
+        while replaced < self.config.max_replacements.unwrap_or(usize::MAX) {
+            let next = matches.iter()
+                .filter_map(|&(pattern, matched)| matched.map(|matched| (pattern, matched)))
+                .min_by_key(|&(pattern, matched)| (matched.start(), pattern));
+            let Some((pattern, matched)) = next else {
+                break;
+            };
+            result.push_str(&input[last..matched.start()]);
+            let start = result.len();
+            let replacement = self.config.replacement(pattern);
+            if self.config.literal {
+                result.push_str(replacement);
+            } else if let Some(captures) = self.patterns[pattern].captures_at(input, matched.start()) {
+                // the groups are captured for the replaced matches only, the same match found again.
+                captures.expand(replacement, &mut result);
+            }
+            offsets.replace(start..result.len(), matched.range());
+            last = matched.end();
+            replaced += 1;
+
+            // an empty match moves the search past the next character.
+            let position = match input[last..].chars().next() {
+                Some(c) if matched.is_empty() => last + c.len_utf8(),
+                None if matched.is_empty() => break,
+                _ => last,
+            };
+            for (pattern, matched) in &mut matches {
+                if matched.is_some_and(|matched| matched.start() < position) {
+                    *matched = self.patterns[*pattern].find_at(input, position);
+                }
+                // as in `find_iter`, no empty match right where the last match ended.
+                if matched.is_some_and(|matched| matched.is_empty() && matched.start() == last) {
+                    *matched = input[last..].chars().next().and_then(|c| self.patterns[*pattern].find_at(input, last + c.len_utf8()));
+                }
+            }
+        }
+        if replaced == 0 {
+            return text;
         }
+        result.push_str(&input[last..]);
+
+        Cow::Owned(result)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(config: RegexConfig, text: &str) -> Cow<'_, str> {
+        RegexCharacterFilter::new(config).unwrap().apply(Cow::Borrowed(text))
+    }
+
+    #[test]
+    fn regex_replacement() {
+        assert_eq!(replace(RegexConfig::new(r"(\d{3})-(?<rest>\d{4})", "$1${rest}"), "call 555-1234 now"), "call 5551234 now");
+        assert_eq!(replace(RegexConfig { literal: true, ..RegexConfig::new(r"(\d+)", "$1") }, "a 12 b"), "a $1 b");
+        assert_eq!(replace(RegexConfig { max_replacements: Some(2), ..RegexConfig::new("a", "b") }, "aaaa"), "bbaa");
+        assert_eq!(replace(RegexConfig { case_insensitive: true, ..RegexConfig::new("straße", "street") }, "STRASSE Straße"), "STRASSE street");
+        assert_eq!(replace(RegexConfig { multi_line: true, ..RegexConfig::new("^> ", "") }, "> a\n> b"), "a\nb");
+        assert_eq!(replace(RegexConfig::new("x*", "-"), "abc"), "-a-b-c-");
+        assert_eq!(replace(RegexConfig::new("x*", "-"), "axxb"), "-a-b-");
+        assert!(matches!(replace(RegexConfig::new("z", "y"), "abc"), Cow::Borrowed(_)));
+
+        assert!(RegexCharacterFilter::new(RegexConfig { unicode: false, ..RegexConfig::new(r"\w", "") }).is_ok());
+        assert!(RegexCharacterFilter::new(RegexConfig { size_limit: 1000, ..RegexConfig::new(r"\w{100}", "") }).is_err());
+
+        let filter = RegexCharacterFilter::from_regex(Regex::new(r"(?i)(\d+)\s*kg").unwrap(), "$1 kilograms").unwrap();
+        assert_eq!(filter.apply(Cow::Borrowed("5KG of 10 kg")), "5 kilograms of 10 kilograms");
+    }
+
+    #[test]
+    fn several_patterns() {
+        let config = RegexConfig {
+            pattern: None,
+            patterns: vec![
+                RegexReplacement { pattern: "ab".to_string(), replacement: "1".to_string() },
+                RegexReplacement { pattern: "abc".to_string(), replacement: "2".to_string() },
+                RegexReplacement { pattern: "bcd".to_string(), replacement: "3".to_string() },
+                RegexReplacement { pattern: r"(\d)".to_string(), replacement: "<$1>".to_string() },
+            ],
+            ..RegexConfig::new("", "")
+        };
+        let filter = RegexCharacterFilter::new(config).unwrap();
+        let mut offsets = OffsetMapping::new();
+        let text = "abcd xbcd 7";
+        let result = filter.apply_with_offsets(Cow::Borrowed(text), &mut offsets);
+        assert_eq!(result, "1cd x3 <7>");
+        assert_eq!(&text[offsets.correct_start(7)..offsets.correct_end(10)], "7");
+
+        let serialized = r#"{"pattern":"colou?r","replacement":"color","case_insensitive":true}"#;
+        let filter: RegexCharacterFilter = serde_json::from_str(serialized).unwrap();
+        assert_eq!(filter.apply(Cow::Borrowed("Colour")), "color");
+        assert!(serde_json::from_str::<RegexCharacterFilter>(r#"{"pattern":"("}"#).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use std::borrow::Cow;
+use std::iter;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use unicode_normalization::{is_nfc, is_nfc_quick, is_nfd, is_nfkc, is_nfkc_quick, is_nfkd, IsNormalized, UnicodeNormalization};
+use unicode_normalization::char::canonical_combining_class;
+use crate::char_filter::CharacterFilterRegistry;
+use crate::char_filter::CharacterFilter;
+use crate::char_filter::offset_mapping::OffsetMapping;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum NormalizationForm {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+    /// NFKC with case folding and removal of default ignorable code points.
+    NfkcCasefold,
+}
+
+/// Normalizes the text to one Unicode normalization form, so that composed
+/// and decomposed spellings of a word become the same.
+#[derive(Clone, JsonSchema, Serialize, Deserialize)]
+pub struct UnicodeNormalizeCharFilter {
+    pub form: NormalizationForm,
+}
+
+impl UnicodeNormalizeCharFilter {
+    fn is_normalized(&self, text: &str) -> bool {
+        match self.form {
+            NormalizationForm::Nfc => is_nfc(text),
+            NormalizationForm::Nfd => is_nfd(text),
+            NormalizationForm::Nfkc => is_nfkc(text),
+            NormalizationForm::Nfkd => is_nfkd(text),
+            NormalizationForm::NfkcCasefold => is_nfkc(text) && text.chars().all(|c| !is_default_ignorable(c) && is_folded(c)),
+        }
+    }
+
+    // whether the normalization of the text before a character is independent of the text after it.
+    fn has_boundary_before(&self, c: char) -> bool {
+        canonical_combining_class(c) == 0 && match self.form {
+            NormalizationForm::Nfd | NormalizationForm::Nfkd => true,
+            NormalizationForm::Nfc => is_nfc_quick(iter::once(c)) == IsNormalized::Yes,
+            NormalizationForm::Nfkc | NormalizationForm::NfkcCasefold => is_nfkc_quick(iter::once(c)) == IsNormalized::Yes,
+        }
+    }
+
+    fn normalize(&self, text: &str, output: &mut String) {
+        match self.form {
+            NormalizationForm::Nfc => output.extend(text.nfc()),
+            NormalizationForm::Nfd => output.extend(text.nfd()),
+            NormalizationForm::Nfkc => output.extend(text.nfkc()),
+            NormalizationForm::Nfkd => output.extend(text.nfkd()),
+            NormalizationForm::NfkcCasefold => {
+                let folded: String = text.nfkc().filter(|&c| !is_default_ignorable(c)).flat_map(fold).collect();
+                output.extend(folded.nfkc());
+            }
+        }
+    }
+}
+
+fn is_folded(c: char) -> bool {
+    let mut folded = fold(c);
+    folded.next() == Some(c) && folded.next().is_none()
+}
+
+// lowercasing, with the full case foldings lowercasing doesn't do.
+fn fold(c: char) -> impl Iterator<Item = char> {
+    let special = match c {
+        'ß' | 'ẞ' => Some("ss"),
+        'ς' => Some("σ"),
+        'ſ' => Some("s"),
+        _ => None,
+    };
+    let lowercase = special.is_none().then(|| c.to_lowercase());
+    special.into_iter().flat_map(str::chars).chain(lowercase.into_iter().flatten())
+}
+
+fn is_default_ignorable(c: char) -> bool {
+    matches!(c,
+        '\u{00AD}' | '\u{034F}' | '\u{061C}' | '\u{115F}'..='\u{1160}' | '\u{17B4}'..='\u{17B5}'
+        | '\u{180B}'..='\u{180F}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{206F}'
+        | '\u{3164}' | '\u{FE00}'..='\u{FE0F}' | '\u{FEFF}' | '\u{FFA0}' | '\u{FFF0}'..='\u{FFF8}'
+        | '\u{1BCA0}'..='\u{1BCA3}' | '\u{1D173}'..='\u{1D17A}' | '\u{E0000}'..='\u{E0FFF}'
+    )
+}
+
+#[typetag::serde]
+impl CharacterFilter for UnicodeNormalizeCharFilter {
+    fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        self.apply_with_offsets(text, &mut OffsetMapping::new())
+    }
+
+    fn apply_with_offsets<'a>(&self, text: Cow<'a, str>, offsets: &mut OffsetMapping) -> Cow<'a, str> {
+        if self.is_normalized(&text) {
+            return text;
+        }
+
+        // normalizes the runs of characters between boundaries separately to know where their output comes from.
+        let mut result = String::with_capacity(text.len());
+        let mut normalized = String::new();
+        let mut start = 0;
+        let boundaries = text.char_indices()
+            .skip(1)
+            .filter(|&(_, c)| self.has_boundary_before(c))
+            .map(|(index, _)| index)
+            .chain([text.len()]);
+        for end in boundaries {
+            let run = &text[start..end];
+            normalized.clear();
+            self.normalize(run, &mut normalized);
+            if normalized != run {
+                offsets.replace(result.len()..result.len() + normalized.len(), start..end);
+            }
+            result.push_str(&normalized);
+            start = end;
+        }
+
+        Cow::Owned(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(form: NormalizationForm, text: &str) -> Cow<'_, str> {
+        UnicodeNormalizeCharFilter { form }.apply(Cow::Borrowed(text))
+    }
+
+    #[test]
+    fn forms() {
+        let decomposed = "Cafe\u{301} ﬁ ①";
+        assert_eq!(normalize(NormalizationForm::Nfc, decomposed), "Café ﬁ ①");
+        assert_eq!(normalize(NormalizationForm::Nfd, "Café"), "Cafe\u{301}");
+        assert_eq!(normalize(NormalizationForm::Nfkc, decomposed), "Café fi 1");
+        assert_eq!(normalize(NormalizationForm::Nfkd, "ﬁ"), "fi");
+        assert_eq!(normalize(NormalizationForm::NfkcCasefold, "Stra\u{DF}e \u{130} Ǆ soft\u{AD}ware"), "strasse i\u{307} dž software");
+
+        assert!(matches!(normalize(NormalizationForm::Nfc, "Café ①"), Cow::Borrowed(_)));
+        assert!(matches!(normalize(NormalizationForm::NfkcCasefold, "café"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn offsets() {
+        let filter = UnicodeNormalizeCharFilter { form: NormalizationForm::NfkcCasefold };
+        let text = "Ｈｅｌｌｏ Cafe\u{301}!";
+        let mut offsets = OffsetMapping::new();
+        let result = filter.apply_with_offsets(Cow::Borrowed(text), &mut offsets);
+        assert_eq!(result, "hello café!");
+
+        assert_eq!(&text[offsets.correct_start(0)..offsets.correct_end(5)], "Ｈｅｌｌｏ");
+        let cafe = result.find("café").unwrap();
+        assert_eq!(&text[offsets.correct_start(cafe)..offsets.correct_end(cafe + "café".len())], "Cafe\u{301}");
+        assert_eq!(&text[offsets.correct_start(result.len() - 1)..], "!");
+    }
+}
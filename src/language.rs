@@ -1,7 +1,9 @@
 use std::str::FromStr;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
 use strum::{IntoStaticStr, EnumString};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, IntoStaticStr, EnumString, Default)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, IntoStaticStr, EnumString, Default, Serialize, Deserialize, JsonSchema)]
 pub enum Language {
     Epo,
     Eng,
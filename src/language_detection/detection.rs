@@ -1,3 +1,6 @@
+use std::collections::BTreeSet;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
 use crate::language::Language;
 use crate::language_detection::LanguageDetector;
 use crate::lazy::Lazy;
@@ -7,7 +10,39 @@ use std::fmt::{Debug, Formatter};
 pub struct LanguageDetection<'detector:'text, 'text> {
     pub text: &'text str,
     pub script: Lazy<Script, DetectScript<'detector, 'text>>,
-    pub language: Lazy<Language, DetectLanguage<'detector, 'text>>,
+    pub language: Lazy<DetectedLanguage, DetectLanguage<'detector, 'text>>,
+}
+
+/// Detected language with the ranked candidates it was picked from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetectedLanguage {
+    pub language: Language,
+    /// Candidate languages with their confidence between 0 and 1, the most likely first.
+    pub candidates: Vec<(Language, f64)>,
+}
+
+impl DetectedLanguage {
+    pub fn new(candidates: Vec<(Language, f64)>) -> DetectedLanguage {
+        DetectedLanguage {
+            language: candidates.first().map_or(Language::Unknown, |&(language, _)| language),
+            candidates,
+        }
+    }
+
+    /// Language known without detection, with a confidence of 1.
+    pub fn certain(language: Language) -> DetectedLanguage {
+        match language {
+            Language::Unknown => DetectedLanguage::new(Vec::new()),
+            language => DetectedLanguage::new(vec![(language, 1.0)]),
+        }
+    }
+
+    pub fn confidence(&self) -> f64 {
+        self.candidates
+            .iter()
+            .find(|&&(candidate, _)| candidate == self.language)
+            .map_or(0.0, |&(_, confidence)| confidence)
+    }
 }
 
 impl<'detector, 'text> LanguageDetection<'detector, 'text> {
@@ -17,15 +52,55 @@ impl<'detector, 'text> LanguageDetection<'detector, 'text> {
     ) -> LanguageDetection<'detector, 'text> {
         LanguageDetection {
             text,
-            language: Lazy::new(DetectLanguage{ text, detector }),
+            language: Lazy::new(DetectLanguage{ text, detector, hint: &LanguageHint::None, language: None }),
             script: Lazy::new(DetectScript{ text, detector }),
         }
     }
 
+    pub fn new_with_script(
+        text: &'text str,
+        script: Script,
+        detector: &'detector dyn LanguageDetector
+    ) -> LanguageDetection<'detector, 'text> {
+        LanguageDetection {
+            text,
+            language: Lazy::new(DetectLanguage{ text, detector, hint: &LanguageHint::None, language: None }),
+            script: Lazy::init(script),
+        }
+    }
+
+    /// Detection whose language is known already, e.g. from a detector
+    /// finding the languages of several segments at once. The candidates are
+    /// detected when first asked for.
+    pub fn new_with_language(
+        text: &'text str,
+        language: Language,
+        script: Script,
+        detector: &'detector dyn LanguageDetector
+    ) -> LanguageDetection<'detector, 'text> {
+        LanguageDetection {
+            text,
+            language: Lazy::new(DetectLanguage{ text, detector, hint: &LanguageHint::None, language: Some(language) }),
+            script: Lazy::init(script),
+        }
+    }
+
     pub fn new_init(
         text: &'text str,
         language: Language,
         script: Script
+    ) -> LanguageDetection<'detector, 'text> {
+        LanguageDetection {
+            text,
+            language: Lazy::init(DetectedLanguage::certain(language)),
+            script: Lazy::init(script),
+        }
+    }
+
+    pub fn new_detected(
+        text: &'text str,
+        language: DetectedLanguage,
+        script: Script
     ) -> LanguageDetection<'detector, 'text> {
         LanguageDetection {
             text,
@@ -38,15 +113,72 @@ impl<'detector, 'text> LanguageDetection<'detector, 'text> {
         self.text
     }
 
-    fn language(&self) -> Language{
-        *self.language
+    pub fn language(&self) -> Language{
+        self.language.language()
+    }
+
+    /// Confidence of the detected language between 0 and 1.
+    pub fn confidence(&self) -> f64{
+        self.language.confidence()
     }
 
-    fn script(&self) -> Script{
+    pub fn candidates(&self) -> &[(Language, f64)]{
+        &self.language.candidates
+    }
+
+    pub fn script(&self) -> Script{
         *self.script
     }
 }
 
+/// What is known of the language of a text ahead of detection, e.g. from metadata.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum LanguageHint {
+    #[default]
+    None,
+    /// Language of the text, used instead of detecting it.
+    Language(Language),
+    /// Languages the text may be in, the detected language is one of them.
+    AllowList(BTreeSet<Language>),
+}
+
+/// Language detections of a text under a [`LanguageHint`]: a hinted language
+/// skips the detector, an allow-list redetects the segments of other languages.
+pub enum HintedLanguageDetections<'detector, 'text, Detections> {
+    Fixed(Option<LanguageDetection<'detector, 'text>>),
+    Detected {
+        detections: Detections,
+        detector: &'detector dyn LanguageDetector,
+        hint: &'detector LanguageHint,
+    },
+}
+
+impl<'detector: 'text, 'text, Detections> Iterator for HintedLanguageDetections<'detector, 'text, Detections>
+    where Detections: Iterator<Item = LanguageDetection<'detector, 'text>>
+{
+    type Item = LanguageDetection<'detector, 'text>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            HintedLanguageDetections::Fixed(detection) => detection.take(),
+            HintedLanguageDetections::Detected { detections, detector, hint } => {
+                let detection = detections.next()?;
+                let LanguageHint::AllowList(languages) = hint else {
+                    return Some(detection);
+                };
+                if detection.language.known_language().is_some_and(|language| languages.contains(&language)) {
+                    return Some(detection);
+                }
+
+                Some(LanguageDetection {
+                    language: Lazy::new(DetectLanguage { detector: *detector, text: detection.text, hint, language: None }),
+                    ..detection
+                })
+            }
+        }
+    }
+}
+
 impl<'text> From<&'text str> for LanguageDetection<'text, 'text> {
     fn from(value: &'text str) -> Self {
         LanguageDetection::new_init(value, Language::Unknown, Script::Unknown)
@@ -81,17 +213,39 @@ impl<'detector, 'text> FnOnce<()> for DetectScript<'detector, 'text> {
 #[derive(Copy, Clone)]
 pub struct DetectLanguage<'detector, 'text>{
     pub detector : &'detector dyn LanguageDetector,
-    pub text: &'text str
+    pub text: &'text str,
+    pub hint: &'detector LanguageHint,
+    /// Language known ahead, only the candidates are left to detect.
+    pub language: Option<Language>,
 }
 
 
 
 impl<'detector, 'text> FnOnce<()> for DetectLanguage<'detector, 'text> {
-    type Output = Language;
+    type Output = DetectedLanguage;
 
     extern "rust-call" fn call_once(self, args: ()) -> Self::Output {
 
-        self.detector.detect_lang(self.text)
+        let candidates = self.detector.detect_lang_with_hint(self.text, self.hint);
+        match self.language {
+            Some(language) => DetectedLanguage { language, candidates },
+            None => DetectedLanguage::new(candidates),
+        }
+    }
+}
+
+impl<'detector, 'text> Lazy<DetectedLanguage, DetectLanguage<'detector, 'text>> {
+    /// The language if known without detection.
+    pub fn known_language(&self) -> Option<Language> {
+        match self.get() {
+            Some(detected) => Some(detected.language),
+            None => self.pending().and_then(|detect| detect.language),
+        }
+    }
+
+    /// The language, detected only if not known already.
+    pub fn language(&self) -> Language {
+        self.known_language().unwrap_or_else(|| Lazy::force(self).language)
     }
 }
 
@@ -0,0 +1,154 @@
+use std::collections::BTreeSet;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::language::Language;
+use crate::language_detection::{BoxableLanguageDetector, BoxLanguageDetector, LanguageDetector, MultipleLanguageDetector};
+use crate::language_detection::detection::{LanguageDetection, LanguageHint};
+use crate::language_detection::LanguageDetectorRegistry;
+use crate::language_detection::segmenter::ScriptSegmenter;
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EnsembleMember {
+    pub detector: BoxLanguageDetector,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// Languages the detector is trusted with, any language when empty.
+    /// Detections of other languages are ignored.
+    #[serde(default)]
+    pub languages: BTreeSet<Language>,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl EnsembleMember {
+    pub fn new<T: BoxableLanguageDetector>(detector: T) -> Self {
+        EnsembleMember {
+            detector: BoxLanguageDetector::new(detector),
+            weight: default_weight(),
+            languages: BTreeSet::new(),
+        }
+    }
+}
+
+/// Detects the language by weighted voting of several detectors, e.g. the
+/// fast whichlang for its languages and lingua for the others.
+///
+/// The detectors are asked in order. One detecting no language, or only
+/// languages it is not trusted with, leaves the decision to the others.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EnsembleDetector {
+    pub detectors: Vec<EnsembleMember>,
+    /// Confidence from which a detection is taken as it is, without asking the
+    /// detectors after it. Detectors without confidence data always reach it.
+    #[serde(default)]
+    pub short_circuit: Option<f64>,
+    #[serde(default)]
+    pub segmenter: ScriptSegmenter,
+}
+
+#[typetag::serde]
+impl LanguageDetector for EnsembleDetector {
+    fn detect_lang(&self, text: &str) -> Language {
+        self.detect_lang_with_confidence(text)
+            .first()
+            .map_or(Language::Unknown, |&(language, _)| language)
+    }
+
+    fn detect_lang_with_confidence(&self, text: &str) -> Vec<(Language, f64)> {
+        self.detect_lang_with_hint(text, &LanguageHint::None)
+    }
+
+    fn detect_lang_with_hint(&self, text: &str, hint: &LanguageHint) -> Vec<(Language, f64)> {
+        let mut votes: Vec<(Language, f64)> = Vec::new();
+        let mut total_weight = 0.0;
+        for member in &self.detectors {
+            let candidates: Vec<_> = member.detector
+                .detect_lang_with_hint(text, hint)
+                .into_iter()
+                .filter(|&(language, confidence)| confidence > 0.0 && (member.languages.is_empty() || member.languages.contains(&language)))
+                .collect();
+            let Some(&(_, confidence)) = candidates.first() else {
+                continue;
+            };
+            if self.short_circuit.is_some_and(|short_circuit| confidence >= short_circuit) {
+                return candidates;
+            }
+
+            total_weight += member.weight;
+            for (language, confidence) in candidates {
+                match votes.iter_mut().find(|(voted, _)| *voted == language) {
+                    Some((_, score)) => *score += member.weight * confidence,
+                    None => votes.push((language, member.weight * confidence)),
+                }
+            }
+        }
+        if total_weight > 0.0 {
+            votes.iter_mut().for_each(|(_, score)| *score /= total_weight);
+        }
+        votes.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        votes
+    }
+}
+
+impl MultipleLanguageDetector for EnsembleDetector {
+    type LanguageDetections<'detector: 'text, 'text> = impl Iterator<Item = LanguageDetection<'detector,'text>>;
+
+    fn detect_multiple_languages<'detector: 'text, 'text>(&'detector self, text: &'text str) -> Self::LanguageDetections<'detector, 'text> {
+        self.segmenter.detect(text, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language_detection::lingua::{LinguaConfig, LinguaDetector};
+    use crate::language_detection::whatlang::WhatLangDetector;
+    use crate::language_detection::whichlang::WhichLangDetector;
+    use super::*;
+
+    #[test]
+    fn voting() {
+        let lingua = LinguaDetector::new(LinguaConfig {
+            languages: BTreeSet::from([Language::Eng, Language::Ukr]),
+            ..LinguaConfig::default()
+        }).unwrap();
+        let detector = EnsembleDetector {
+            detectors: vec![
+                EnsembleMember { languages: BTreeSet::from([Language::Eng, Language::Fra]), ..EnsembleMember::new(WhichLangDetector::default()) },
+                EnsembleMember { weight: 2.0, ..EnsembleMember::new(lingua) },
+            ],
+            short_circuit: None,
+            segmenter: ScriptSegmenter::default(),
+        };
+
+        // whichlang does not know Ukrainian, lingua decides alone.
+        let candidates = detector.detect_lang_with_confidence("Погода в Києві сьогодні чудова");
+        assert_eq!(candidates[0].0, Language::Ukr);
+        assert_eq!(detector.detect_lang("The weather in London is nice today"), Language::Eng);
+
+        let detector = EnsembleDetector { short_circuit: Some(0.9), ..detector };
+        assert_eq!(detector.detect_lang_with_confidence("The weather in London is nice today"), [(Language::Eng, 1.0)]);
+        assert_eq!(detector.detect_lang("Погода в Києві сьогодні чудова"), Language::Ukr);
+
+        let languages: Vec<_> = detector.detect_multiple_languages("Погода в Києві сьогодні чудова The weather in London is nice today")
+            .map(|detection| detection.language())
+            .collect();
+        assert_eq!(languages, [Language::Ukr, Language::Eng]);
+    }
+
+    #[test]
+    fn serialize() {
+        let detector = EnsembleDetector {
+            detectors: vec![EnsembleMember::new(WhichLangDetector::default()), EnsembleMember { weight: 0.5, ..EnsembleMember::new(WhatLangDetector::default()) }],
+            short_circuit: Some(0.8),
+            segmenter: ScriptSegmenter { max_segment_length: None, ..ScriptSegmenter::default() },
+        };
+        let serialized = serde_json::to_string(&detector).unwrap();
+        assert_eq!(serialized, r#"{"detectors":[{"detector":{"WhichLangDetector":{"segmenter":{"min_run_length":5,"max_segment_length":500}}},"weight":1.0,"languages":[]},{"detector":{"WhatLangDetector":{"segmenter":{"min_run_length":5,"max_segment_length":500}}},"weight":0.5,"languages":[]}],"short_circuit":0.8,"segmenter":{"min_run_length":5,"max_segment_length":null}}"#);
+
+        let deserialized: EnsembleDetector = serde_json::from_str(r#"{"detectors":[{"detector":{"WhatLangDetector":{}}}]}"#).unwrap();
+        assert_eq!(deserialized.detect_lang("The weather in London is nice today"), Language::Eng);
+    }
+}
@@ -0,0 +1,54 @@
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::language::Language;
+use crate::language_detection::{first_script, LanguageDetector, MultipleLanguageDetector};
+use crate::language_detection::detection::LanguageDetection;
+use crate::language_detection::LanguageDetectorRegistry;
+use crate::script::{script_option, Script};
+
+/// Reports the same language for every text, for fields whose language is
+/// known ahead. The script is detected unless it is given too.
+#[derive(Copy, Clone, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct FixedLanguageDetector {
+    pub language: Language,
+    #[serde(default, with = "script_option")]
+    #[schemars(with = "Option<String>")]
+    pub script: Option<Script>,
+}
+
+#[typetag::serde]
+impl LanguageDetector for FixedLanguageDetector {
+    fn detect_script(&self, text: &str) -> Script {
+        self.script.unwrap_or_else(|| first_script(text))
+    }
+
+    fn detect_lang(&self, _text: &str) -> Language {
+        self.language
+    }
+}
+
+impl MultipleLanguageDetector for FixedLanguageDetector {
+    type LanguageDetections<'detector: 'text, 'text> = std::iter::Once<LanguageDetection<'detector, 'text>>;
+
+    fn detect_multiple_languages<'detector: 'text, 'text>(&'detector self, text: &'text str) -> Self::LanguageDetections<'detector, 'text> {
+        std::iter::once(self.detect(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed() {
+        let detector = FixedLanguageDetector { language: Language::Fra, script: None };
+        let detections: Vec<_> = detector.detect_multiple_languages("Погода в Москве").collect();
+        assert_eq!(detections.len(), 1);
+        assert_eq!((detections[0].language(), detections[0].script(), detections[0].confidence()), (Language::Fra, Script::Cyrillic, 1.0));
+
+        let serialized = serde_json::to_string(&FixedLanguageDetector { script: Some(Script::Latin), ..detector }).unwrap();
+        assert_eq!(serialized, r#"{"language":"Fra","script":"Latin"}"#);
+        let deserialized: FixedLanguageDetector = serde_json::from_str(r#"{"language":"Deu"}"#).unwrap();
+        assert_eq!((deserialized.language, deserialized.script), (Language::Deu, None));
+    }
+}
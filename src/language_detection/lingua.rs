@@ -2,29 +2,143 @@ use crate::language::Language;
 use crate::language_detection::detection::LanguageDetection;
 use crate::language_detection::{LanguageDetector, MultipleLanguageDetector};
 use crate::language_detection::LanguageDetectorRegistry;
-use std::sync::Arc;
+use std::collections::BTreeSet;
+use std::io;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use schemars::gen::SchemaGenerator;
 use schemars::JsonSchema;
+use schemars::schema::Schema;
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LinguaConfig {
+    /// Languages to choose from, all the languages lingua knows when empty.
+    #[serde(default)]
+    pub languages: BTreeSet<Language>,
+    /// Distance between the two most likely languages, from 0 to 0.99, below
+    /// which the language is left unknown.
+    #[serde(default)]
+    pub minimum_relative_distance: f64,
+    /// Uses trigrams only, faster and lighter but less accurate on short texts.
+    #[serde(default)]
+    pub low_accuracy: bool,
+    /// Loads the language models when built rather than on first use.
+    #[serde(default = "default_preload")]
+    pub preload: bool,
+}
+
+fn default_preload() -> bool {
+    true
+}
+
+impl Default for LinguaConfig {
+    fn default() -> Self {
+        LinguaConfig {
+            languages: BTreeSet::new(),
+            minimum_relative_distance: 0.0,
+            low_accuracy: false,
+            preload: default_preload(),
+        }
+    }
+}
+
+// detectors built so far, shared by the detectors with the same config.
+static DETECTORS: Mutex<Vec<(LinguaConfig, Weak<lingua::LanguageDetector>)>> = Mutex::new(Vec::new());
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "LinguaConfig", into = "LinguaConfig")]
 pub struct LinguaDetector {
-    #[serde(skip, default = "build_lingua")]
+    config: LinguaConfig,
     inner: Arc<lingua::LanguageDetector>,
 }
 
 impl LinguaDetector {
-    pub fn new() -> Self {
-        Self {
-            inner: Arc::new(
-                lingua::LanguageDetectorBuilder::from_all_languages()
-                    .with_preloaded_language_models()
-                    .build(),
-            ),
+    pub fn new(config: LinguaConfig) -> io::Result<Self> {
+        if !(0.0..=0.99).contains(&config.minimum_relative_distance) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "minimum relative distance must be between 0 and 0.99"));
+        }
+        let languages = config.languages
+            .iter()
+            .map(|&language| lingua_language(language)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{language:?} is not supported by lingua"))))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if let Some(inner) = cached_detector(&config) {
+            return Ok(LinguaDetector { config, inner });
+        }
+
+        // built without holding the lock, models may take seconds to load.
+        let mut builder = if languages.is_empty() {
+            lingua::LanguageDetectorBuilder::from_all_languages()
+        } else {
+            lingua::LanguageDetectorBuilder::from_languages(&languages)
+        };
+        builder.with_minimum_relative_distance(config.minimum_relative_distance);
+        if config.low_accuracy {
+            builder.with_low_accuracy_mode();
         }
+        if config.preload {
+            builder.with_preloaded_language_models();
+        }
+        let built = Arc::new(builder.build());
+
+        let mut detectors = DETECTORS.lock().unwrap_or_else(PoisonError::into_inner);
+        // a detector with the same config may have been built meanwhile.
+        let inner = match find_detector(&detectors, &config) {
+            Some(inner) => inner,
+            None => {
+                detectors.push((config.clone(), Arc::downgrade(&built)));
+                built
+            }
+        };
+
+        Ok(LinguaDetector { config, inner })
     }
 }
 
+fn cached_detector(config: &LinguaConfig) -> Option<Arc<lingua::LanguageDetector>> {
+    let mut detectors = DETECTORS.lock().unwrap_or_else(PoisonError::into_inner);
+    detectors.retain(|(_, detector)| detector.strong_count() > 0);
+    find_detector(&detectors, config)
+}
 
+fn find_detector(detectors: &[(LinguaConfig, Weak<lingua::LanguageDetector>)], config: &LinguaConfig) -> Option<Arc<lingua::LanguageDetector>> {
+    detectors.iter()
+        .find(|(cached, _)| cached == config)
+        .and_then(|(_, detector)| detector.upgrade())
+}
+
+fn lingua_language(language: Language) -> Option<lingua::Language> {
+    let iso_code = lingua::IsoCode639_3::from_str(language.into()).ok()?;
+    lingua::Language::all()
+        .into_iter()
+        .find(|candidate| candidate.iso_code_639_3() == iso_code)
+}
+
+impl TryFrom<LinguaConfig> for LinguaDetector {
+    type Error = io::Error;
+
+    fn try_from(config: LinguaConfig) -> Result<Self, Self::Error> {
+        LinguaDetector::new(config)
+    }
+}
+
+impl From<LinguaDetector> for LinguaConfig {
+    fn from(detector: LinguaDetector) -> Self {
+        detector.config
+    }
+}
+
+impl JsonSchema for LinguaDetector {
+    fn schema_name() -> String {
+        "LinguaDetector".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        LinguaConfig::json_schema(gen)
+    }
+}
 
 #[typetag::serde]
 impl LanguageDetector for LinguaDetector {
@@ -37,6 +151,15 @@ impl LanguageDetector for LinguaDetector {
             .detect_language_of(text)
             .map_or(Language::Unknown, Into::into)
     }
+
+    fn detect_lang_with_confidence(&self, text: &str) -> Vec<(Language, f64)> {
+        self.inner
+            .compute_language_confidence_values(text)
+            .into_iter()
+            .filter(|&(_, confidence)| confidence > 0.0)
+            .map(|(language, confidence)| (language.into(), confidence))
+            .collect()
+    }
 }
 
 impl MultipleLanguageDetector for LinguaDetector {
@@ -57,11 +180,7 @@ impl MultipleLanguageDetector for LinguaDetector {
             };
             let text = &text[start..end];
 
-            LanguageDetection::new_init(
-                text,
-                Language::from(x.language()),
-                self.detect_script(text),
-            )
+            LanguageDetection::new_with_language(text, Language::from(x.language()), self.detect_script(text), self)
         })
     }
 }
@@ -70,26 +189,69 @@ impl MultipleLanguageDetector for LinguaDetector {
 #[cfg(test)]
 mod tests {
     use schemars::schema_for;
-    use crate::language_detection::lingua::LinguaDetector;
-    use crate::language_detection::{BoxableLanguageDetector, BoxLanguageDetector, MultipleLanguageDetector};
-    use crate::language_detection::whatlang::WhatLangDetector;
+    use crate::language::Language;
+    use crate::language_detection::lingua::{LinguaConfig, LinguaDetector};
+    use crate::language_detection::{BoxableLanguageDetector, BoxLanguageDetector, LanguageDetector, MultipleLanguageDetector};
+    use super::*;
 
     #[test]
     fn lingua() {
         let text = "Οι θερμοκρασίες είναι σπάνια υπερβολικές στις παραθαλάσσιες περιοχές. 제119조 ① 대한민국의 경제질서는 개인과 기업의 경제상의 자유와 창의를 존중함을 기본으로 한다. La ville avait d'abord été nommée My name is Aleksey, What is your name?";
 
-        let detector = LinguaDetector::new();
+        let detector = LinguaDetector::new(LinguaConfig::default()).unwrap();
         let lang = detector.detect_multiple_languages(text);
         for each in lang {
             println!("{:?}", each);
         }
+
+        let detection = detector.detect("La ville avait d'abord été nommée");
+        assert_eq!(detection.language(), Language::Fra);
+        assert!(detection.candidates().len() > 1);
+        assert!(detection.candidates().windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(detection.confidence(), detection.candidates()[0].1);
+
+        let detections: Vec<_> = detector.detect_multiple_languages("La ville avait d'abord été nommée").collect();
+        // the language is known without computing the candidates.
+        assert_eq!(detections[0].language(), Language::Fra);
+        assert!(detections[0].language.get().is_none());
+        assert!(detections.iter().all(|detection| detection.candidates().len() > 1 && detection.confidence() < 1.0));
+    }
+
+    #[test]
+    fn config() {
+        let config = LinguaConfig {
+            languages: BTreeSet::from([Language::Fra, Language::Deu]),
+            low_accuracy: true,
+            preload: false,
+            ..LinguaConfig::default()
+        };
+        let detector = LinguaDetector::new(config.clone()).unwrap();
+        assert_eq!(detector.detect_lang("The weather is nice today"), Language::Deu);
+        assert_eq!(detector.detect_lang("La ville avait d'abord été nommée"), Language::Fra);
+
+        let serialized = serde_json::to_string(&detector).unwrap();
+        assert_eq!(serialized, r#"{"languages":["Fra","Deu"],"minimum_relative_distance":0.0,"low_accuracy":true,"preload":false}"#);
+        let deserialized: LinguaDetector = serde_json::from_str(&serialized).unwrap();
+        assert!(Arc::ptr_eq(&detector.inner, &deserialized.inner));
+        assert!(!Arc::ptr_eq(&detector.inner, &LinguaDetector::new(LinguaConfig { low_accuracy: false, ..config }).unwrap().inner));
+
+        // detectors built at the same time with the same config end up shared.
+        let config = LinguaConfig { languages: BTreeSet::from([Language::Ita, Language::Spa]), ..config };
+        let detectors: Vec<_> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..4).map(|_| scope.spawn(|| LinguaDetector::new(config.clone()).unwrap())).collect();
+            threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+        });
+        assert!(detectors.windows(2).all(|pair| Arc::ptr_eq(&pair[0].inner, &pair[1].inner)));
+
+        assert!(LinguaDetector::new(LinguaConfig { languages: BTreeSet::from([Language::Unknown]), ..LinguaConfig::default() }).is_err());
+        assert!(LinguaDetector::new(LinguaConfig { minimum_relative_distance: 1.5, ..LinguaConfig::default() }).is_err());
     }
 
     #[test]
     fn serialize() {
         let text = "Οι θερμοκρασίες είναι σπάνια υπερβολικές στις παραθαλάσσιες περιοχές. 제119조 ① 대한민국의 경제질서는 개인과 기업의 경제상의 자유와 창의를 존중함을 기본으로 한다. La ville avait d'abord été nommée My name is Aleksey, What is your name?";
 
-        let detector = LinguaDetector::new();;
+        let detector = LinguaDetector::new(LinguaConfig::default()).unwrap();
         let lang = detector.detect_multiple_languages(text);
         for each in lang {
             println!("{:?}", each);
@@ -112,25 +274,3 @@ mod tests {
     }
 }
 
-
-fn build_lingua() -> Arc<lingua::LanguageDetector>{
-    Arc::new(
-        lingua::LanguageDetectorBuilder::from_all_languages()
-            .with_preloaded_language_models()
-            .build(),
-    )
-}
-
-impl From<()> for LinguaDetector {
-    fn from(value: ()) -> Self {
-        LinguaDetector{
-            inner: build_lingua()
-        }
-    }
-}
-
-impl Into<()> for LinguaDetector {
-    fn into(self) -> () {
-        ()
-    }
-}
\ No newline at end of file
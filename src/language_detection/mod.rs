@@ -2,20 +2,30 @@ pub mod detection;
 pub mod whichlang;
 pub mod whatlang;
 pub mod lingua;
+pub mod ensemble;
+pub mod fixed;
+pub mod segmenter;
 
+use std::collections::BTreeSet;
 use schemars::gen::SchemaGenerator;
 use schemars::JsonSchema;
 use schemars::schema::Schema;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use unicode_script::UnicodeScript;
-use typetag::__private::erased_serde;
 use crate::char_filter::CharacterFilter;
 use crate::script::Script;
 use crate::language::Language;
-use crate::language_detection::detection::LanguageDetection;
+use crate::language_detection::detection::{DetectedLanguage, DetectScript, HintedLanguageDetections, LanguageDetection, LanguageHint};
+use crate::lazy::Lazy;
 
 pub struct BoxLanguageDetector(Box<dyn BoxableLanguageDetector>);
 
+impl BoxLanguageDetector{
+    pub fn new<T: BoxableLanguageDetector>(detector: T) -> Self{
+        BoxLanguageDetector(Box::new(detector))
+    }
+}
+
 #[typetag::serde(receiver = BoxableLanguageDetector)]
 pub trait LanguageDetector: 'static + Send + Sync{
 
@@ -24,14 +34,56 @@ pub trait LanguageDetector: 'static + Send + Sync{
     }
 
     fn detect_script(&self, text: &str) -> Script {
-        text.chars()
-            .map(|char| char.script())
-            .find(|&script| script != Script::Common)
-            .unwrap_or(Script::Unknown)
+        first_script(text)
     }
     fn detect_lang(&self, text: &str) -> Language;
 
+    /// Candidate languages of the text with their confidence between 0 and 1,
+    /// the most likely first. Detectors without confidence data report their
+    /// single guess with a confidence of 1.
+    fn detect_lang_with_confidence(&self, text: &str) -> Vec<(Language, f64)> {
+        match self.detect_lang(text) {
+            Language::Unknown => Vec::new(),
+            language => vec![(language, 1.0)],
+        }
+    }
+
+    /// Candidate languages of the text given what is known of its language
+    /// ahead: a hinted language is taken as it is, an allow-list keeps the
+    /// candidates among its languages. Detectors able to choose among the
+    /// allowed languages only should do so; here, when none of the candidates
+    /// is allowed, an allow-list of one language gives that language.
+    fn detect_lang_with_hint(&self, text: &str, hint: &LanguageHint) -> Vec<(Language, f64)> {
+        match hint {
+            LanguageHint::None => self.detect_lang_with_confidence(text),
+            LanguageHint::Language(language) => DetectedLanguage::certain(*language).candidates,
+            LanguageHint::AllowList(languages) => {
+                let candidates = self.detect_lang_with_confidence(text)
+                    .into_iter()
+                    .filter(|(language, _)| languages.contains(language))
+                    .collect();
+                or_only_allowed(candidates, languages)
+            }
+        }
+    }
+
+}
+
+/// The candidates detected under an allow-list, or its language when it has
+/// only one and none of the candidates is allowed.
+pub(crate) fn or_only_allowed(candidates: Vec<(Language, f64)>, languages: &BTreeSet<Language>) -> Vec<(Language, f64)> {
+    match languages.first() {
+        Some(&language) if candidates.is_empty() && languages.len() == 1 => DetectedLanguage::certain(language).candidates,
+        _ => candidates,
+    }
+}
 
+/// Script of the first character of the text with a script of its own.
+pub(crate) fn first_script(text: &str) -> Script {
+    text.chars()
+        .map(|char| char.script())
+        .find(|&script| script != Script::Common)
+        .unwrap_or(Script::Unknown)
 }
 
 
@@ -41,6 +93,27 @@ pub trait MultipleLanguageDetector: LanguageDetector + Clone{
         &'detector self,
         text: &'text str,
     ) -> Self::LanguageDetections<'detector, 'text>;
+
+    /// Detections of the language segments of the text under the hint. A
+    /// hinted language makes the whole text one segment, without detection.
+    fn detect_multiple_languages_with_hint<'detector: 'text, 'text>(
+        &'detector self,
+        text: &'text str,
+        hint: &'detector LanguageHint,
+    ) -> HintedLanguageDetections<'detector, 'text, Self::LanguageDetections<'detector, 'text>> where Self: Sized {
+        match hint {
+            LanguageHint::Language(language) => HintedLanguageDetections::Fixed(Some(LanguageDetection {
+                text,
+                script: Lazy::new(DetectScript { text, detector: self }),
+                language: Lazy::init(DetectedLanguage::certain(*language)),
+            })),
+            hint => HintedLanguageDetections::Detected {
+                detections: self.detect_multiple_languages(text),
+                detector: self,
+                hint,
+            },
+        }
+    }
 }
 
 pub trait BoxableLanguageDetector: 'static + Send + Sync + typetag::Serialize {
@@ -54,6 +127,12 @@ pub trait BoxableLanguageDetector: 'static + Send + Sync + typetag::Serialize {
     #[doc(hidden)]
     fn box_detect_lang(&self, text: &str) -> Language;
 
+    #[doc(hidden)]
+    fn box_detect_lang_with_confidence(&self, text: &str) -> Vec<(Language, f64)>;
+
+    #[doc(hidden)]
+    fn box_detect_lang_with_hint(&self, text: &str, hint: &LanguageHint) -> Vec<(Language, f64)>;
+
     #[doc(hidden)]
     fn box_detect_multiple_languages<'detector: 'text, 'text>(
         &'detector self,
@@ -81,6 +160,14 @@ impl<T: MultipleLanguageDetector + Serialize> BoxableLanguageDetector for T {
         <T as LanguageDetector>::detect_lang(self, text)
     }
 
+    fn box_detect_lang_with_confidence(&self, text: &str) -> Vec<(Language, f64)> {
+        <T as LanguageDetector>::detect_lang_with_confidence(self, text)
+    }
+
+    fn box_detect_lang_with_hint(&self, text: &str, hint: &LanguageHint) -> Vec<(Language, f64)> {
+        <T as LanguageDetector>::detect_lang_with_hint(self, text, hint)
+    }
+
     fn box_detect_multiple_languages<'detector: 'text, 'text>(
         &'detector self,
         text: &'text str,
@@ -101,15 +188,23 @@ impl<T: MultipleLanguageDetector + Serialize> BoxableLanguageDetector for T {
 
 impl LanguageDetector for BoxLanguageDetector {
     fn detect<'detector, 'str>(&'detector self, text: &'str str) -> LanguageDetection<'detector, 'str> {
-        <Self as BoxableLanguageDetector>::box_detect(self, text)
+        self.0.box_detect(text)
     }
 
     fn detect_script(&self, text: &str) -> Script {
-        <Self as BoxableLanguageDetector>::box_detect_script(self, text)
+        self.0.box_detect_script(text)
     }
 
     fn detect_lang(&self, text: &str) -> Language {
-        <Self as BoxableLanguageDetector>::box_detect_lang(self, text)
+        self.0.box_detect_lang(text)
+    }
+
+    fn detect_lang_with_confidence(&self, text: &str) -> Vec<(Language, f64)> {
+        self.0.box_detect_lang_with_confidence(text)
+    }
+
+    fn detect_lang_with_hint(&self, text: &str, hint: &LanguageHint) -> Vec<(Language, f64)> {
+        self.0.box_detect_lang_with_hint(text, hint)
     }
 
     fn type_name(&self) -> &'static str {
@@ -137,7 +232,7 @@ impl Clone for BoxLanguageDetector {
 
 impl Serialize for BoxLanguageDetector {
     fn serialize<S>(&self, mut serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-        erased_serde::serialize(&*self.0, serializer)
+        Serialize::serialize(&*self.0, serializer)
     }
 }
 
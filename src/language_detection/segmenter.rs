@@ -0,0 +1,249 @@
+use std::ops::Range;
+use std::{ptr, vec};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use unicode_script::UnicodeScript;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::language_detection::detection::{DetectedLanguage, LanguageDetection};
+use crate::language_detection::LanguageDetector;
+use crate::script::Script;
+
+/// Splits a text into runs of one script, to detect the language of each.
+///
+/// Common and Inherited characters, e.g. spaces, brackets, digits and
+/// combining marks, join the neighbouring run, and Han, Hiragana and Katakana
+/// make one run. Runs of fewer letters than `min_run_length` keep their script
+/// but their language is detected together with the run before them, so that
+/// `iPad` in `Я купил iPad` is detected as Russian.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScriptSegmenter {
+    #[serde(default = "default_min_run_length")]
+    pub min_run_length: usize,
+    /// Length in characters above which a run is split into chunks of whole
+    /// sentences, so that the language of each chunk is detected on its own.
+    #[serde(default = "default_max_segment_length")]
+    pub max_segment_length: Option<usize>,
+}
+
+fn default_min_run_length() -> usize {
+    5
+}
+
+fn default_max_segment_length() -> Option<usize> {
+    Some(500)
+}
+
+impl Default for ScriptSegmenter {
+    fn default() -> Self {
+        ScriptSegmenter {
+            min_run_length: default_min_run_length(),
+            max_segment_length: default_max_segment_length(),
+        }
+    }
+}
+
+/// A run of one script and the text its language is detected from.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Segment<'text> {
+    pub text: &'text str,
+    pub script: Script,
+    /// The text of the segment with the short runs merged with it, or the
+    /// chunk of sentences it is part of.
+    pub context: &'text str,
+}
+
+#[derive(Clone)]
+struct Run {
+    start: usize,
+    end: usize,
+    script: Script,
+    letters: usize,
+}
+
+fn same_run(a: Script, b: Script) -> bool {
+    let japanese = |script| matches!(script, Script::Han | Script::Hiragana | Script::Katakana);
+    a == b || (japanese(a) && japanese(b))
+}
+
+impl ScriptSegmenter {
+    pub fn segment<'text>(&self, text: &'text str) -> vec::IntoIter<Segment<'text>> {
+        let runs = runs(text);
+
+        let mut groups: Vec<Run> = Vec::with_capacity(runs.len());
+        for run in &runs {
+            match groups.last_mut() {
+                Some(last) if same_run(last.script, run.script) || run.letters < self.min_run_length => {
+                    last.end = run.end;
+                    last.letters += run.letters;
+                }
+                _ => groups.push(run.clone()),
+            }
+        }
+        // a short first run joins the run after it.
+        if groups.len() > 1 && groups[0].letters < self.min_run_length {
+            let first = groups.remove(0);
+            groups[0].start = first.start;
+            groups[0].letters += first.letters;
+        }
+
+        let mut segments = Vec::with_capacity(runs.len());
+        let mut runs = runs.iter().peekable();
+        for group in groups {
+            for chunk in self.chunks(text, group.start..group.end) {
+                while let Some(run) = runs.peek().filter(|run| run.start < chunk.end) {
+                    let range = run.start.max(chunk.start)..run.end.min(chunk.end);
+                    segments.push(Segment { text: &text[range], script: run.script, context: &text[chunk.clone()] });
+                    if run.end > chunk.end {
+                        // the rest of the run is in the next chunk.
+                        break;
+                    }
+                    runs.next();
+                }
+            }
+        }
+
+        segments.into_iter()
+    }
+
+    /// Detections of the segments of the text. The language of segments
+    /// sharing a context is detected once for all of them.
+    pub fn detect<'detector: 'text, 'text>(
+        &self,
+        text: &'text str,
+        detector: &'detector dyn LanguageDetector,
+    ) -> impl Iterator<Item = LanguageDetection<'detector, 'text>> {
+        let mut context: Option<(&'text str, DetectedLanguage)> = None;
+        self.segment(text).map(move |segment| {
+            if segment.context.len() == segment.text.len() {
+                return LanguageDetection::new_with_script(segment.text, segment.script, detector);
+            }
+            let language = match &context {
+                Some((text, language)) if ptr::eq(*text, segment.context) => language.clone(),
+                _ => {
+                    let language = DetectedLanguage::new(detector.detect_lang_with_confidence(segment.context));
+                    context = Some((segment.context, language.clone()));
+                    language
+                }
+            };
+            LanguageDetection::new_detected(segment.text, language, segment.script)
+        })
+    }
+
+    fn chunks(&self, text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+        let Some(max_length) = self.max_segment_length else {
+            return vec![range];
+        };
+        let mut chunks = Vec::new();
+        let mut start = range.start;
+        let mut length = 0;
+        for (offset, sentence) in text[range.clone()].split_sentence_bound_indices() {
+            let sentence_length = sentence.chars().count();
+            if length > 0 && length + sentence_length > max_length {
+                chunks.push(start..range.start + offset);
+                start = range.start + offset;
+                length = 0;
+            }
+            length += sentence_length;
+        }
+        chunks.push(start..range.end);
+
+        chunks
+    }
+}
+
+fn runs(text: &str) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    // where the characters after the last letter may be split: after their last whitespace.
+    let mut split = None;
+    for (index, char) in text.char_indices() {
+        let script = char.script();
+        if matches!(script, Script::Common | Script::Inherited | Script::Unknown) {
+            if char.is_whitespace() {
+                split = Some(index + char.len_utf8());
+            }
+            continue;
+        }
+
+        match runs.last_mut() {
+            Some(run) if same_run(run.script, script) => run.letters += 1,
+            Some(run) => {
+                let start = split.unwrap_or(index);
+                run.end = start;
+                runs.push(Run { start, end: text.len(), script, letters: 1 });
+            }
+            None => runs.push(Run { start: 0, end: text.len(), script, letters: 1 }),
+        }
+        split = None;
+    }
+    if runs.is_empty() && !text.is_empty() {
+        runs.push(Run { start: 0, end: text.len(), script: Script::Unknown, letters: 0 });
+    }
+
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::Language;
+    use crate::language_detection::whatlang::WhatLangDetector;
+    use super::*;
+
+    fn segment(segmenter: ScriptSegmenter, text: &str) -> Vec<(&str, Script, &str)> {
+        segmenter.segment(text).map(|segment| (segment.text, segment.script, segment.context)).collect()
+    }
+
+    #[test]
+    fn script_runs() {
+        let segmenter = ScriptSegmenter::default();
+        assert_eq!(segment(segmenter, "Погода в Москве (Moscow) 2024, the weather"), [
+            ("Погода в Москве ", Script::Cyrillic, "Погода в Москве "),
+            ("(Moscow) 2024, the weather", Script::Latin, "(Moscow) 2024, the weather"),
+        ]);
+        let text = "Я купил iPad вчера";
+        assert_eq!(segment(segmenter, text), [
+            ("Я купил ", Script::Cyrillic, text),
+            ("iPad ", Script::Latin, text),
+            ("вчера", Script::Cyrillic, text),
+        ]);
+        assert_eq!(segment(segmenter, "the word سلام here"), [
+            ("the word ", Script::Latin, "the word سلام here"),
+            ("سلام ", Script::Arabic, "the word سلام here"),
+            ("here", Script::Latin, "the word سلام here"),
+        ]);
+        assert_eq!(segment(segmenter, "東京タワーはきれい"), [("東京タワーはきれい", Script::Han, "東京タワーはきれい")]);
+        // a combining acute accent is Inherited.
+        assert_eq!(segment(segmenter, "Москва\u{301} London"), [("Москва\u{301} ", Script::Cyrillic, "Москва\u{301} "), ("London", Script::Latin, "London")]);
+        assert_eq!(segment(segmenter, "42 !"), [("42 !", Script::Unknown, "42 !")]);
+        assert!(segment(segmenter, "").is_empty());
+
+        let segmenter = ScriptSegmenter { min_run_length: 0, ..segmenter };
+        assert_eq!(segment(segmenter, "Я купил iPad"), [("Я купил ", Script::Cyrillic, "Я купил "), ("iPad", Script::Latin, "iPad")]);
+    }
+
+    #[test]
+    fn sentence_chunks() {
+        let segmenter = ScriptSegmenter { max_segment_length: Some(30), ..ScriptSegmenter::default() };
+        let segments: Vec<_> = segment(segmenter, "The weather is nice. Il fait beau. Es ist schön.").into_iter().map(|(text, _, _)| text).collect();
+        assert_eq!(segments, ["The weather is nice. ", "Il fait beau. Es ist schön."]);
+
+        let segmenter = ScriptSegmenter { max_segment_length: None, ..segmenter };
+        assert_eq!(segment(segmenter, "The weather is nice. Il fait beau. Es ist schön.").len(), 1);
+
+        let deserialized: ScriptSegmenter = serde_json::from_str(r#"{"max_segment_length":null}"#).unwrap();
+        assert_eq!(deserialized, ScriptSegmenter { min_run_length: 5, max_segment_length: None });
+    }
+
+    #[test]
+    fn detect() {
+        let detector = WhatLangDetector::default();
+        let detections: Vec<_> = ScriptSegmenter::default()
+            .detect("Я купил новый iPad вчера вечером", &detector)
+            .map(|detection| (detection.text(), detection.script(), detection.language()))
+            .collect();
+        assert_eq!(detections, [
+            ("Я купил новый ", Script::Cyrillic, Language::Rus),
+            ("iPad ", Script::Latin, Language::Rus),
+            ("вчера вечером", Script::Cyrillic, Language::Rus),
+        ]);
+    }
+}
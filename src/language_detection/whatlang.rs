@@ -1,13 +1,16 @@
 use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
-use unicode_script::{Script, UnicodeScript};
 use crate::language::Language;
-use crate::language_detection::detection::{LanguageDetection};
-use crate::language_detection::{LanguageDetector, MultipleLanguageDetector};
+use crate::language_detection::detection::{DetectedLanguage, LanguageDetection, LanguageHint};
+use crate::language_detection::{or_only_allowed, LanguageDetector, MultipleLanguageDetector};
 use crate::language_detection::LanguageDetectorRegistry;
+use crate::language_detection::segmenter::ScriptSegmenter;
 
-#[derive(Copy, Clone, Serialize, Deserialize , JsonSchema, Debug)]
-pub struct WhatLangDetector{}
+#[derive(Copy, Clone, Default, Serialize, Deserialize , JsonSchema, Debug)]
+pub struct WhatLangDetector{
+    #[serde(default)]
+    pub segmenter: ScriptSegmenter,
+}
 
 
 
@@ -16,51 +19,52 @@ impl LanguageDetector for WhatLangDetector {
     fn detect_lang(&self, text: &str) -> Language {
         whatlang::detect_lang(text).map_or(Language::Unknown, Into::into)
     }
+
+    fn detect_lang_with_confidence(&self, text: &str) -> Vec<(Language, f64)> {
+        whatlang::detect(text).map_or(Vec::new(), |info| vec![(info.lang().into(), info.confidence())])
+    }
+
+    fn detect_lang_with_hint(&self, text: &str, hint: &LanguageHint) -> Vec<(Language, f64)> {
+        let languages = match hint {
+            LanguageHint::None => return self.detect_lang_with_confidence(text),
+            LanguageHint::Language(language) => return DetectedLanguage::certain(*language).candidates,
+            LanguageHint::AllowList(languages) => languages,
+        };
+        // whatlang picks among the allowed languages itself.
+        let allowlist = languages
+            .iter()
+            .filter_map(|&language| whatlang::Lang::from_code(<&str>::from(language).to_lowercase()))
+            .collect();
+        let candidates = whatlang::Detector::with_allowlist(allowlist)
+            .detect(text)
+            .map_or(Vec::new(), |info| vec![(info.lang().into(), info.confidence())]);
+
+        or_only_allowed(candidates, languages)
+    }
 }
 
 impl MultipleLanguageDetector for WhatLangDetector{
     type LanguageDetections<'detector: 'text, 'text> = impl Iterator<Item = LanguageDetection<'detector,'text>>;
 
     fn detect_multiple_languages<'detector: 'text, 'text>(&'detector self, text: &'text str) -> Self::LanguageDetections<'detector, 'text> {
-        let mut chars = text.char_indices().peekable();
-        let (mut prev_index, mut prev_script) = chars
-            .peek()
-            .map(|&(index, char)| (index, char.script()))
-            .unwrap_or_default();
-        std::iter::from_fn(move || {
-            while let Some(_) = chars.next() {
-                let Some(&(index, char)) = chars.peek() else {
-                    return text.get(prev_index..);
-                };
-                let script = char.script();
-
-                if script != Script::Common && script != prev_script {
-                    if prev_script != Script::Common {
-                        let text = text.get(prev_index..index);
-                        prev_script = script;
-                        prev_index = index;
-                        return text;
-                    };
-                    prev_script = script;
-                }
-            }
-            return None;
-        })
-            .map(move |text| self.detect(text))
+        self.segmenter.detect(text, self)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
     use schemars::schema_for;
-    use crate::language_detection::{BoxableLanguageDetector, BoxLanguageDetector, MultipleLanguageDetector};
+    use crate::language::Language;
+    use crate::language_detection::{BoxableLanguageDetector, BoxLanguageDetector, LanguageDetector, MultipleLanguageDetector};
+    use crate::language_detection::detection::LanguageHint;
     use crate::language_detection::whatlang::WhatLangDetector;
 
     #[test]
     fn whatlang() {
         let text = "Οι θερμοκρασίες είναι σπάνια υπερβολικές στις παραθαλάσσιες περιοχές. 제119조 ① 대한민국의 경제질서는 개인과 기업의 경제상의 자유와 창의를 존중함을 기본으로 한다. La ville avait d'abord été nommée My name is Aleksey, What is your name?";
 
-        let detector = WhatLangDetector{};
+        let detector = WhatLangDetector::default();
         let lang = detector.detect_multiple_languages(text);
         for each in lang {
             println!("{:?}", each);
@@ -72,7 +76,7 @@ mod tests {
     fn serialize() {
         let text = "Οι θερμοκρασίες είναι σπάνια υπερβολικές στις παραθαλάσσιες περιοχές. 제119조 ① 대한민국의 경제질서는 개인과 기업의 경제상의 자유와 창의를 존중함을 기본으로 한다. La ville avait d'abord été nommée My name is Aleksey, What is your name?";
 
-        let detector = WhatLangDetector{};
+        let detector = WhatLangDetector::default();
         let lang = detector.detect_multiple_languages(text);
         for each in lang {
             println!("{:?}", each);
@@ -86,6 +90,10 @@ mod tests {
             println!("{:?}", each);
         }
 
+        let candidates = deserialized.detect_lang_with_confidence("My name is Aleksey, What is your name?");
+        assert_eq!(candidates.len(), 1);
+        assert!(candidates[0].1 > 0.0 && candidates[0].1 <= 1.0);
+
         let schema = schema_for!(BoxLanguageDetector);
         let schema = serde_json::to_string_pretty(&schema).unwrap();;
         println!("{}", schema);
@@ -93,5 +101,20 @@ mod tests {
 
 
     }
-}
 
+    #[test]
+    fn allow_list() {
+        let detector = WhatLangDetector::default();
+        let text = "Gaur eguraldi ona dago Donostian";
+
+        // whatlang does not know Basque, the only allowed language is taken.
+        let hint = LanguageHint::AllowList(BTreeSet::from([Language::Eus]));
+        assert_eq!(detector.detect_lang_with_hint(text, &hint), [(Language::Eus, 1.0)]);
+        let hint = LanguageHint::AllowList(BTreeSet::from([Language::Eus, Language::Cym]));
+        assert!(detector.detect_lang_with_hint(text, &hint).is_empty());
+
+        let hint = LanguageHint::AllowList(BTreeSet::from([Language::Eus, Language::Ukr]));
+        let candidates = detector.detect_lang_with_hint("Погода в Києві сьогодні чудова", &hint);
+        assert_eq!(candidates[0].0, Language::Ukr);
+    }
+}
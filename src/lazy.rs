@@ -83,8 +83,9 @@ impl<T, F: FnOnce() -> T> Lazy<T, F> {
 }
 
 impl<T, F> Lazy<T, F> {
+    /// The value if it is initialized already, without forcing it.
     #[inline]
-    fn get(&self) -> Option<&T> {
+    pub fn get(&self) -> Option<&T> {
         // SAFETY:
         // This is sound for the same reason as in `force`: once the state is
         // initialized, it will not be mutably accessed again, so this reference
@@ -95,6 +96,17 @@ impl<T, F> Lazy<T, F> {
             _ => None,
         }
     }
+
+    /// A copy of the initializing function if the value is not initialized yet.
+    #[inline]
+    pub fn pending(&self) -> Option<F> where F: Copy {
+        // SAFETY: as in `get`, the function is copied out before any access that could initialize the value.
+        let state = unsafe { &*self.state.get() };
+        match state {
+            State::Uninit(f) => Some(*f),
+            _ => None,
+        }
+    }
 }
 
 impl<T, F: FnOnce() -> T> Deref for Lazy<T, F> {
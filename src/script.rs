@@ -1 +1,37 @@
-pub use unicode_script::Script;
\ No newline at end of file
+use std::collections::HashSet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+pub use unicode_script::Script;
+
+/// (De)serializes a set of scripts by their full Unicode names, e.g. `"Cyrillic"`.
+pub mod script_set {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(scripts: &HashSet<Script>, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut names: Vec<&str> = scripts.iter().map(|script| script.full_name()).collect();
+        names.sort_unstable();
+        serializer.collect_seq(names)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashSet<Script>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|name| Script::from_full_name(&name).ok_or_else(|| D::Error::custom(format!("unknown script `{}`", name))))
+            .collect()
+    }
+}
+
+/// (De)serializes an optional script by its full Unicode name.
+pub mod script_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(script: &Option<Script>, serializer: S) -> Result<S::Ok, S::Error> {
+        script.map(|script| script.full_name()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Script>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|name| Script::from_full_name(&name).ok_or_else(|| D::Error::custom(format!("unknown script `{}`", name))))
+            .transpose()
+    }
+}
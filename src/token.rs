@@ -5,12 +5,12 @@ use anymap::{CloneAny};
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 use crate::language::Language;
-use crate::language_detection::detection::{DetectLanguage, DetectScript};
+use crate::language_detection::detection::{DetectedLanguage, DetectLanguage, DetectScript};
 use crate::lazy::Lazy;
 use crate::script::Script;
 
 pub type LazyScript<'tokenizer> = Rc<Lazy<Script, DetectScript<'tokenizer, 'tokenizer>>>;
-pub type LazyLanguage<'tokenizer> = Rc<Lazy<Language, DetectLanguage<'tokenizer, 'tokenizer>>>;
+pub type LazyLanguage<'tokenizer> = Rc<Lazy<DetectedLanguage, DetectLanguage<'tokenizer, 'tokenizer>>>;
 
 pub type OwnedToken<'tokenizer> = Token<String, LazyLanguage<'tokenizer>, LazyScript<'tokenizer>, AnyMap>;
 pub type BorrowedToken<'borrow, 'tokenizer> = Token<&'borrow mut String, &'borrow LazyLanguage<'tokenizer>, &'borrow LazyScript<'tokenizer>, &'borrow mut AnyMap>;
@@ -62,6 +62,39 @@ impl<'tokenizer, Str, Lang, Script, Attr> Token<Str, Lang, Script, Attr>
         matches!(self.token_kind, TokenKind::Word(_))
     }
 
+    /// Script of the token, forcing the lazy detection if needed.
+    pub fn script(&self) -> crate::script::Script {
+        ***self.script.borrow()
+    }
+
+    /// Language of the token, forcing the lazy detection if needed.
+    pub fn language(&self) -> Language {
+        self.language.borrow().language()
+    }
+
+    /// Confidence of the language of the token between 0 and 1.
+    pub fn language_confidence(&self) -> f64 {
+        self.language.borrow().confidence()
+    }
+
+    /// Candidate languages of the token with their confidence, the most likely first.
+    pub fn language_candidates<'a>(&'a self) -> &'a [(Language, f64)] where 'tokenizer: 'a {
+        &self.language.borrow().candidates
+    }
+
+    /// Whether the token is protected from modification by a [`Keyword`] marker.
+    pub fn is_keyword(&self) -> bool {
+        self.attributes.borrow().contains::<Keyword>()
+    }
+
+    pub fn set_keyword(&mut self, keyword: bool) {
+        if keyword {
+            self.attributes.borrow_mut().insert(Keyword);
+        } else {
+            self.attributes.borrow_mut().remove::<Keyword>();
+        }
+    }
+
     pub fn separator_kind(&self) -> Option<SeparatorKind> {
         if let TokenKind::Separator(s) = self.token_kind {
             Some(s)
@@ -84,6 +117,10 @@ impl<'tokenizer, Str, Lang, Script, Attr> Token<Str, Lang, Script, Attr>
     }
 }
 
+/// Attribute marking a token that modifying filters (stemming, folding, decompounding, ...) must leave alone.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Keyword;
+
 #[derive(Copy, Clone, Debug, Serialize,Deserialize)]
 pub enum TokenKind{
     Word(TokenFlags),
@@ -127,7 +164,7 @@ pub enum SeparatorKind{
              offset_from: self.offset_from,
              offset_to: self.offset_to,
              position: self.position,
-             position_length: self.position,
+             position_length: self.position_length,
              token_kind: self.token_kind,
              script: &self.script,
              language: &self.language,
@@ -160,7 +197,7 @@ impl<'borrow, 'tokenizer> BorrowedToken<'borrow, 'tokenizer>{
             offset_from: self.offset_from,
             offset_to: self.offset_to,
             position: self.position,
-            position_length: self.position,
+            position_length: self.position_length,
             token_kind: self.token_kind,
             script: Rc::clone(self.script),
             language: Rc::clone(self.language),
@@ -168,6 +205,17 @@ impl<'borrow, 'tokenizer> BorrowedToken<'borrow, 'tokenizer>{
         }
     }
 
+    /// Copies the token into `slot`, reusing the allocation of a previously stored token.
+    pub fn clone_into_slot<'slot>(&self, slot: &'slot mut Option<OwnedToken<'tokenizer>>) -> &'slot mut OwnedToken<'tokenizer>{
+        match slot {
+            Some(owned_token) => {
+                self.clone_into_owned(owned_token);
+                owned_token
+            }
+            None => slot.insert(self.to_owned())
+        }
+    }
+
     pub fn clone_into_owned(&self, mut owned_token: &mut OwnedToken<'tokenizer>){
         owned_token.text.clear();
         owned_token.text.push_str(self.text);
@@ -0,0 +1,139 @@
+use std::mem;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::script::Script;
+use crate::token::BorrowedToken;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Normalizes Arabic script tokens: strips harakat and tatweel and unifies
+/// alef, hamza, yeh, kaf and teh marbuta variants.
+///
+/// Only tokens whose script is [`Script::Arabic`] are touched.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ArabicNormalizationFilter {
+    #[serde(default = "crate::token_filter::enabled")]
+    pub remove_harakat: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub remove_tatweel: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub normalize_alef: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub normalize_hamza: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub normalize_yeh: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub normalize_kaf: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub normalize_teh_marbuta: bool,
+}
+
+impl Default for ArabicNormalizationFilter {
+    fn default() -> Self {
+        ArabicNormalizationFilter {
+            remove_harakat: true,
+            remove_tatweel: true,
+            normalize_alef: true,
+            normalize_hamza: true,
+            normalize_yeh: true,
+            normalize_kaf: true,
+            normalize_teh_marbuta: true,
+        }
+    }
+}
+
+#[typetag::serde]
+impl TokenFilter for ArabicNormalizationFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = ArabicNormalizationTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        ArabicNormalizationTokenStream {
+            tail: token_stream,
+            filter: self,
+            buffer: String::with_capacity(100)
+        }
+    }
+}
+
+pub(crate) const TATWEEL: char = '\u{0640}';
+
+/// Arabic diacritics: tanween, harakat, shadda, sukun, hamza marks,
+/// superscript alef and the Quranic annotation signs.
+pub(crate) fn is_harakah(c: char) -> bool {
+    matches!(c, '\u{0610}'..='\u{061A}' | '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06ED}')
+}
+
+impl ArabicNormalizationFilter {
+    fn normalize(&self, text: &str, output: &mut String) {
+        output.clear();
+        for c in text.chars() {
+            let c = match c {
+                c if self.remove_harakat && is_harakah(c) => continue,
+                TATWEEL if self.remove_tatweel => continue,
+                // alef with madda, hamza above, hamza below and wasla
+                '\u{0622}' | '\u{0623}' | '\u{0625}' | '\u{0671}' if self.normalize_alef => '\u{0627}',
+                // waw with hamza above
+                '\u{0624}' if self.normalize_hamza => '\u{0648}',
+                // yeh with hamza above
+                '\u{0626}' if self.normalize_hamza => '\u{064A}',
+                // alef maksura and farsi yeh
+                '\u{0649}' | '\u{06CC}' if self.normalize_yeh => '\u{064A}',
+                // keheh
+                '\u{06A9}' if self.normalize_kaf => '\u{0643}',
+                '\u{0629}' if self.normalize_teh_marbuta => '\u{0647}',
+                c => c
+            };
+            output.push(c);
+        }
+    }
+}
+
+pub struct ArabicNormalizationTokenStream<'token, T> {
+    buffer: String,
+    filter: &'token ArabicNormalizationFilter,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for ArabicNormalizationTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let token = self.tail.next()?;
+        if !token.is_keyword() && !token.text.is_ascii() && token.script() == Script::Arabic {
+            self.filter.normalize(token.text, &mut self.buffer);
+            mem::swap(token.text, &mut self.buffer);
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::Language;
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn arabic_normalization() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = ArabicNormalizationFilter::default();
+        let text = "أَحْمَد إسلام آمن مكتبـــة مستشفى مسؤول";
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Ara, Script::Arabic)));
+
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["احمد", "اسلام", "امن", "مكتبه", "مستشفي", "مسوول"]);
+    }
+
+    #[test]
+    fn other_scripts_untouched() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = ArabicNormalizationFilter::default();
+        let text = "Ёлка أَحْمَد";
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Rus, Script::Cyrillic)));
+
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["Ёлка", "أَحْمَد"]);
+    }
+}
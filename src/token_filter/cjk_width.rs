@@ -0,0 +1,125 @@
+use std::mem;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::script::Script;
+use crate::token::BorrowedToken;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Folds fullwidth ASCII variants into basic latin and halfwidth katakana
+/// and hangul into their regular forms. Halfwidth voiced sound marks are
+/// composed with the preceding kana.
+///
+/// Only tokens whose script is Han, Hiragana, Katakana or Hangul are touched.
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CjkWidthFilter {}
+
+#[typetag::serde]
+impl TokenFilter for CjkWidthFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = CjkWidthTokenStream<T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        CjkWidthTokenStream {
+            tail: token_stream,
+            buffer: String::with_capacity(100)
+        }
+    }
+}
+
+pub(crate) fn is_cjk_script(script: Script) -> bool {
+    matches!(script, Script::Han | Script::Hiragana | Script::Katakana | Script::Hangul)
+}
+
+// Katakana for U+FF65..=U+FF9F, the last two entries are the combining voiced sound marks.
+const HALFWIDTH_KATAKANA: [char; 59] = [
+    '\u{30FB}', '\u{30F2}', '\u{30A1}', '\u{30A3}', '\u{30A5}', '\u{30A7}', '\u{30A9}', '\u{30E3}', '\u{30E5}',
+    '\u{30E7}', '\u{30C3}', '\u{30FC}', '\u{30A2}', '\u{30A4}', '\u{30A6}', '\u{30A8}', '\u{30AA}', '\u{30AB}',
+    '\u{30AD}', '\u{30AF}', '\u{30B1}', '\u{30B3}', '\u{30B5}', '\u{30B7}', '\u{30B9}', '\u{30BB}', '\u{30BD}',
+    '\u{30BF}', '\u{30C1}', '\u{30C4}', '\u{30C6}', '\u{30C8}', '\u{30CA}', '\u{30CB}', '\u{30CC}', '\u{30CD}',
+    '\u{30CE}', '\u{30CF}', '\u{30D2}', '\u{30D5}', '\u{30D8}', '\u{30DB}', '\u{30DE}', '\u{30DF}', '\u{30E0}',
+    '\u{30E1}', '\u{30E2}', '\u{30E4}', '\u{30E6}', '\u{30E8}', '\u{30E9}', '\u{30EA}', '\u{30EB}', '\u{30EC}',
+    '\u{30ED}', '\u{30EF}', '\u{30F3}', '\u{3099}', '\u{309A}',
+];
+
+const FULLWIDTH_SIGNS: [char; 7] = ['\u{00A2}', '\u{00A3}', '\u{00AC}', '\u{00AF}', '\u{00A6}', '\u{00A5}', '\u{20A9}'];
+
+fn fold_width(c: char) -> char {
+    let code = c as u32;
+    match code {
+        0xFF01..=0xFF5E => char::from_u32(code - 0xFEE0).unwrap_or(c),
+        0x3000 => ' ',
+        0xFFE0..=0xFFE6 => FULLWIDTH_SIGNS[(code - 0xFFE0) as usize],
+        0xFF65..=0xFF9F => HALFWIDTH_KATAKANA[(code - 0xFF65) as usize],
+        0xFFA0 => '\u{3164}',
+        0xFFA1..=0xFFBE => char::from_u32(code - 0xFFA1 + 0x3131).unwrap_or(c),
+        0xFFC2..=0xFFC7 => char::from_u32(code - 0xFFC2 + 0x314F).unwrap_or(c),
+        0xFFCA..=0xFFCF => char::from_u32(code - 0xFFCA + 0x3155).unwrap_or(c),
+        0xFFD2..=0xFFD7 => char::from_u32(code - 0xFFD2 + 0x315B).unwrap_or(c),
+        0xFFDA..=0xFFDC => char::from_u32(code - 0xFFDA + 0x3161).unwrap_or(c),
+        _ => c
+    }
+}
+
+// composes katakana with a following voiced (U+3099) or semi-voiced (U+309A) sound mark.
+fn compose_voiced(base: char, mark: char) -> Option<char> {
+    let code = base as u32;
+    match (mark, base) {
+        ('\u{3099}', 'カ' | 'キ' | 'ク' | 'ケ' | 'コ' | 'サ' | 'シ' | 'ス' | 'セ' | 'ソ' | 'タ' | 'チ' | 'ツ' | 'テ' | 'ト' | 'ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ') => char::from_u32(code + 1),
+        ('\u{3099}', 'ウ') => Some('ヴ'),
+        ('\u{3099}', 'ワ' | 'ヰ' | 'ヱ' | 'ヲ') => char::from_u32(code + 8),
+        ('\u{309A}', 'ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ') => char::from_u32(code + 2),
+        _ => None
+    }
+}
+
+fn fold_cjk_width(text: &str, output: &mut String) {
+    output.clear();
+    for c in text.chars().map(fold_width) {
+        if matches!(c, '\u{3099}' | '\u{309A}') {
+            if let Some(composed) = output.chars().next_back().and_then(|base| compose_voiced(base, c)) {
+                output.pop();
+                output.push(composed);
+                continue;
+            }
+        }
+        output.push(c);
+    }
+}
+
+pub struct CjkWidthTokenStream<T> {
+    buffer: String,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for CjkWidthTokenStream<T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let token = self.tail.next()?;
+        if !token.is_keyword() && !token.text.is_ascii() && is_cjk_script(token.script()) {
+            fold_cjk_width(token.text, &mut self.buffer);
+            mem::swap(token.text, &mut self.buffer);
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::Language;
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn cjk_width() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = CjkWidthFilter {};
+        let text = "ｶﾞｲﾄﾞﾌﾞｯｸ Ｔｏｋｙｏ２０２４ ﾊﾟﾝ 東京";
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Jpn, Script::Katakana)));
+
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["ガイドブック", "Tokyo2024", "パン", "東京"]);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::{BTreeSet, HashSet};
+use polonius_the_crab::{polonius, polonius_return};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::inline_dyn::Dynamic;
+use crate::language::Language;
+use crate::script::{script_set, Script};
+use crate::token::BorrowedToken;
+use crate::token_filter::token_channel::TokenChannel;
+use crate::token_filter::token_filter_layer::{BoxTokenFilterLayer, TokenFilterLayers};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum TokenKindPredicate {
+    Word,
+    Separator,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub enum TokenPredicate {
+    Language(BTreeSet<Language>),
+    Script(
+        #[serde(with = "script_set")]
+        #[schemars(with = "BTreeSet<String>")]
+        HashSet<Script>
+    ),
+    Kind(TokenKindPredicate),
+    Pattern(
+        #[serde(with = "serde_regex")]
+        #[schemars(with = "String")]
+        Regex
+    ),
+    /// Length of the token text in characters, both bounds inclusive.
+    Length {
+        #[serde(default)]
+        min: usize,
+        #[serde(default)]
+        max: Option<usize>,
+    },
+    /// Confidence of the detected language of the token, at least `min`.
+    LanguageConfidence {
+        min: f64,
+    },
+}
+
+impl TokenPredicate {
+    pub fn matches(&self, token: &BorrowedToken<'_, '_>) -> bool {
+        match self {
+            TokenPredicate::Language(languages) => languages.contains(&token.language()),
+            TokenPredicate::Script(scripts) => scripts.contains(&token.script()),
+            TokenPredicate::Kind(TokenKindPredicate::Word) => token.is_word(),
+            TokenPredicate::Kind(TokenKindPredicate::Separator) => token.is_separator(),
+            TokenPredicate::Pattern(pattern) => pattern.is_match(token.text),
+            TokenPredicate::Length { min, max } => {
+                let length = token.text.chars().count();
+                length >= *min && max.is_none_or(|max| length <= max)
+            }
+            TokenPredicate::LanguageConfidence { min } => token.language_confidence() >= *min,
+        }
+    }
+}
+
+/// Applies the wrapped filters only to tokens matching the predicate, other
+/// tokens pass through untouched.
+///
+/// The wrapped filters see every matching token as a stream of its own, so
+/// filters looking across several tokens, e.g. fingerprints or shingles, see
+/// each matching token alone.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConditionalFilter {
+    pub predicate: TokenPredicate,
+    pub filters: BoxTokenFilterLayer,
+}
+
+#[typetag::serde]
+impl TokenFilter for ConditionalFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = ConditionalTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        let channel = TokenChannel::new();
+        ConditionalTokenStream {
+            tail: token_stream,
+            filter: self,
+            channel,
+            inner: None,
+        }
+    }
+}
+
+pub struct ConditionalTokenStream<'token, T> {
+    filter: &'token ConditionalFilter,
+    channel: TokenChannel<'token>,
+    // the filters applied to the last matching token, until drained.
+    inner: Option<Dynamic<dyn TokenStream<'token> + 'token>>,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for ConditionalTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        loop {
+            if this.inner.is_some() {
+                polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                    if let Some(token) = this.inner.as_mut().and_then(TokenStream::next) {
+                        polonius_return!(Some(token));
+                    }
+                });
+                this.inner = None;
+            }
+
+            polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                match this.tail.next() {
+                    None => polonius_return!(None),
+                    Some(token) if !this.filter.predicate.matches(&token) => polonius_return!(Some(token)),
+                    Some(token) => this.channel.send(&token),
+                }
+            });
+            this.inner = Some(this.filter.filters.apply_layer(this.channel.receiver()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::token_filter::fingerprint::FingerprintFilter;
+    use crate::token_filter::limit_token_count::LimitTokenCountFilter;
+    use crate::token_filter::lower_case::LowerCaseFilter;
+    use crate::token_filter::token_filter_layer::BaseLevel;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn apply(filter: &ConditionalFilter, text: &str) -> Vec<String> {
+        let tokenizer = WhitespaceTokenizer {};
+        filter.apply(tokenizer.tokenize(text)).as_iter().map(|token| token.text).collect()
+    }
+
+    #[test]
+    fn conditional() {
+        let filters = BaseLevel.wrap_dynamic_layer(LowerCaseFilter {});
+        let filter = ConditionalFilter {
+            predicate: TokenPredicate::Length { min: 4, max: None },
+            filters,
+        };
+
+        assert_eq!(apply(&filter, "The QUICK Fox JUMPS"), ["The", "quick", "Fox", "jumps"]);
+
+        let filter = ConditionalFilter {
+            predicate: TokenPredicate::Pattern(Regex::new("^[A-Z]+$").unwrap()),
+            ..filter
+        };
+        assert_eq!(apply(&filter, "The QUICK Fox JUMPS"), ["The", "quick", "Fox", "jumps"]);
+
+        // the language of plain text is unknown, with no confidence at all.
+        let filter = ConditionalFilter {
+            predicate: TokenPredicate::LanguageConfidence { min: 0.5 },
+            ..filter
+        };
+        assert_eq!(apply(&filter, "The QUICK Fox JUMPS"), ["The", "QUICK", "Fox", "JUMPS"]);
+    }
+
+    #[test]
+    fn stateful_filters() {
+        // every matching token gets fresh filters, none is lost.
+        let filter = ConditionalFilter {
+            predicate: TokenPredicate::Length { min: 4, max: None },
+            filters: BaseLevel.wrap_dynamic_layer(FingerprintFilter::default()),
+        };
+        assert_eq!(apply(&filter, "the quick fox jumps over"), ["the", "quick", "fox", "jumps", "over"]);
+
+        let filter = ConditionalFilter {
+            filters: BaseLevel.wrap_dynamic_layer(LimitTokenCountFilter { max_token_count: 1, consume_all_tokens: false }),
+            ..filter
+        };
+        assert_eq!(apply(&filter, "the quick fox jumps over"), ["the", "quick", "fox", "jumps", "over"]);
+    }
+
+    #[test]
+    fn serialize() {
+        let filter = ConditionalFilter {
+            predicate: TokenPredicate::Script(HashSet::from([Script::Latin, Script::Cyrillic])),
+            filters: BaseLevel.wrap_dynamic_layer(LowerCaseFilter {}),
+        };
+        let serialized = serde_json::to_string(&filter).unwrap();
+        assert_eq!(serialized, r#"{"predicate":{"Script":["Cyrillic","Latin"]},"filters":[{"LowerCaseFilter":{}}]}"#);
+
+        let deserialized: ConditionalFilter = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+        assert!(serde_json::from_str::<ConditionalFilter>(r#"{"predicate":{"Script":["Klingon"]},"filters":[]}"#).is_err());
+    }
+}
@@ -0,0 +1,461 @@
+use std::collections::VecDeque;
+use polonius_the_crab::{polonius, polonius_return};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::language::Language;
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Attribute holding the ISO-8601 form of a recognized date or time:
+/// `2024-03-12`, `2024-03`, `--03-12`, `2024-03-12T22:30:00` or `22:30:00`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IsoDateTime(pub String);
+
+/// Order of the components of all numeric dates like `12/03/2024`.
+/// `2024-03-12` is always read year first.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+}
+
+/// Recognizes date and time expressions like `12/03/2024`, `March 12th, 2024`,
+/// `12 марта 2024 г.` or `10:30 pm` and merges them into a single token
+/// spanning the original ones, tagged with an [`IsoDateTime`].
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DateRecognitionFilter {
+    /// Languages of the month names to recognize, all supported ones if empty.
+    #[serde(default)]
+    pub languages: Vec<Language>,
+    /// Defaults to month first for English tokens and day first otherwise.
+    #[serde(default)]
+    pub numeric_order: Option<DateOrder>,
+}
+
+#[typetag::serde]
+impl TokenFilter for DateRecognitionFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = DateRecognitionTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        DateRecognitionTokenStream {
+            tail: token_stream,
+            filter: self,
+            window: VecDeque::new(),
+            words: Vec::new(),
+            token: None,
+        }
+    }
+}
+
+type Months = [&'static [&'static str]; 12];
+
+const MONTHS: [(Language, Months); 8] = [
+    (Language::Eng, [
+        &["january", "jan"], &["february", "feb"], &["march", "mar"], &["april", "apr"], &["may"], &["june", "jun"],
+        &["july", "jul"], &["august", "aug"], &["september", "sep", "sept"], &["october", "oct"], &["november", "nov"], &["december", "dec"],
+    ]),
+    (Language::Rus, [
+        &["январь", "января", "янв"], &["февраль", "февраля", "фев", "февр"], &["март", "марта", "мар"], &["апрель", "апреля", "апр"],
+        &["май", "мая"], &["июнь", "июня", "июн"], &["июль", "июля", "июл"], &["август", "августа", "авг"],
+        &["сентябрь", "сентября", "сен", "сент"], &["октябрь", "октября", "окт"], &["ноябрь", "ноября", "ноя", "нояб"], &["декабрь", "декабря", "дек"],
+    ]),
+    (Language::Ukr, [
+        &["січень", "січня", "січ"], &["лютий", "лютого", "лют"], &["березень", "березня", "бер"], &["квітень", "квітня", "квіт"],
+        &["травень", "травня", "трав"], &["червень", "червня", "черв"], &["липень", "липня", "лип"], &["серпень", "серпня", "серп"],
+        &["вересень", "вересня", "вер"], &["жовтень", "жовтня", "жовт"], &["листопад", "листопада", "лист"], &["грудень", "грудня", "груд"],
+    ]),
+    (Language::Deu, [
+        &["januar", "jänner", "jan"], &["februar", "feb"], &["märz", "mär"], &["april", "apr"], &["mai"], &["juni", "jun"],
+        &["juli", "jul"], &["august", "aug"], &["september", "sep", "sept"], &["oktober", "okt"], &["november", "nov"], &["dezember", "dez"],
+    ]),
+    (Language::Fra, [
+        &["janvier", "janv"], &["février", "fevrier", "févr"], &["mars"], &["avril", "avr"], &["mai"], &["juin"],
+        &["juillet", "juil"], &["août", "aout"], &["septembre", "sept"], &["octobre", "oct"], &["novembre", "nov"], &["décembre", "decembre", "déc"],
+    ]),
+    (Language::Spa, [
+        &["enero", "ene"], &["febrero", "feb"], &["marzo", "mar"], &["abril", "abr"], &["mayo", "may"], &["junio", "jun"],
+        &["julio", "jul"], &["agosto", "ago"], &["septiembre", "setiembre", "sep", "sept"], &["octubre", "oct"], &["noviembre", "nov"], &["diciembre", "dic"],
+    ]),
+    (Language::Ita, [
+        &["gennaio", "gen"], &["febbraio", "feb"], &["marzo", "mar"], &["aprile", "apr"], &["maggio", "mag"], &["giugno", "giu"],
+        &["luglio", "lug"], &["agosto", "ago"], &["settembre", "set"], &["ottobre", "ott"], &["novembre", "nov"], &["dicembre", "dic"],
+    ]),
+    (Language::Por, [
+        &["janeiro", "jan"], &["fevereiro", "fev"], &["março", "marco", "mar"], &["abril", "abr"], &["maio", "mai"], &["junho", "jun"],
+        &["julho", "jul"], &["agosto", "ago"], &["setembro", "set"], &["outubro", "out"], &["novembro", "nov"], &["dezembro", "dez"],
+    ]),
+];
+
+// words joining the parts of a date, `12th of March`, `12 de marzo de 2024`.
+const CONNECTORS: [&str; 3] = ["of", "de", "del"];
+// words following a year, `2024 г.`, `2024 года`.
+const YEAR_SUFFIXES: [&str; 4] = ["г.", "г", "года", "год"];
+// words between a date and a time, `March 12 at 10:30`.
+const TIME_CONNECTORS: [&str; 4] = ["at", "um", "à", "в"];
+
+// the longest expression is `12th of March of 2024 г. at 10:30 pm`.
+const MAX_WORDS: usize = 10;
+
+#[derive(Copy, Clone)]
+struct Date {
+    year: Option<i32>,
+    month: u32,
+    day: Option<u32>,
+}
+
+#[derive(Copy, Clone)]
+struct Time {
+    hour: u32,
+    minute: u32,
+    second: u32,
+}
+
+fn days_in_month(year: Option<i32>, month: u32) -> u32 {
+    match month {
+        2 => match year {
+            Some(year) if year % 4 != 0 || year % 100 == 0 && year % 400 != 0 => 28,
+            _ => 29
+        },
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+impl Date {
+    fn new(year: Option<i32>, month: u32, day: Option<u32>) -> Option<Date> {
+        let valid = (1..=12).contains(&month) && day.is_none_or(|day| day >= 1 && day <= days_in_month(year, month));
+        valid.then_some(Date { year, month, day })
+    }
+}
+
+fn digits(text: &str, lengths: std::ops::RangeInclusive<usize>) -> Option<u32> {
+    (lengths.contains(&text.len()) && text.bytes().all(|b| b.is_ascii_digit())).then(|| text.parse().ok())?
+}
+
+fn parse_day(word: &str) -> Option<u32> {
+    let word = word.trim_end_matches('.');
+    let word = ["st", "nd", "rd", "th", "er", "-го", "-е", "-ого"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix))
+        .unwrap_or(word);
+    digits(word, 1..=2).filter(|day| (1..=31).contains(day))
+}
+
+fn parse_year(word: &str) -> Option<i32> {
+    let word = word.trim_end_matches('.');
+    let word = word.strip_suffix('г').unwrap_or(word);
+    digits(word, 4..=4).map(|year| year as i32)
+}
+
+fn parse_time(words: &[String]) -> Option<(usize, Time)> {
+    let word = words.first()?;
+    let (clock, mut meridiem) = match word.find(|c: char| c.is_alphabetic()) {
+        Some(index) => (&word[..index], Some(&word[index..])),
+        None => (word.as_str(), None),
+    };
+    let mut parts = clock.split(':');
+    let mut hour = digits(parts.next()?, 1..=2)?;
+    let minute = digits(parts.next()?, 2..=2)?;
+    let second = parts.next().map_or(Some(0), |second| digits(second, 2..=2))?;
+    if parts.next().is_some() || minute > 59 || second > 59 {
+        return None;
+    }
+
+    let mut len = 1;
+    if meridiem.is_none() {
+        if let Some(next) = words.get(1).filter(|next| is_meridiem(next)) {
+            meridiem = Some(next);
+            len += 1;
+        }
+    }
+    match meridiem {
+        Some(meridiem) if !is_meridiem(meridiem) => return None,
+        Some(meridiem) => {
+            if !(1..=12).contains(&hour) {
+                return None;
+            }
+            hour %= 12;
+            if meridiem.starts_with('p') {
+                hour += 12;
+            }
+        }
+        None if hour > 23 => return None,
+        None => {}
+    }
+
+    Some((len, Time { hour, minute, second }))
+}
+
+fn is_meridiem(word: &str) -> bool {
+    matches!(word, "am" | "pm" | "a.m." | "p.m.")
+}
+
+impl DateRecognitionFilter {
+    fn months(&self) -> impl Iterator<Item = &'static Months> + '_ {
+        MONTHS.iter()
+            .filter(|(language, _)| self.languages.is_empty() || self.languages.contains(language))
+            .map(|(_, months)| months)
+    }
+
+    fn parse_month(&self, word: &str) -> Option<u32> {
+        let word = word.trim_end_matches('.');
+        self.months()
+            .find_map(|months| months.iter().position(|forms| forms.contains(&word)))
+            .map(|month| month as u32 + 1)
+    }
+
+    fn may_start(&self, text: &str) -> bool {
+        match text.chars().next() {
+            Some(c) if c.is_ascii_digit() => true,
+            Some(c) if c.is_alphabetic() && text.len() <= 20 => {
+                let word = text.to_lowercase();
+                self.parse_month(word.trim_end_matches(',')).is_some()
+            }
+            _ => false
+        }
+    }
+
+    // `12/03/2024`, `12.03.2024`, `2024-03-12` and `2024-03-12T10:30:00`.
+    fn parse_numeric_date(&self, word: &str, language: Language) -> Option<(Date, Option<Time>)> {
+        let word = word.trim_end_matches('.');
+        let separator = word.chars().find(|c| matches!(c, '/' | '.' | '-'))?;
+        let (date, time) = match word.split_once('t') {
+            Some((date, time)) if separator == '-' => (date, Some(parse_time(&[time.to_string()])?.1)),
+            _ => (word, None),
+        };
+        let mut parts = date.split(separator);
+        let (first, second, third) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() {
+            return None;
+        }
+
+        if let Some(year) = parse_year(first).filter(|_| first.len() == 4) {
+            return Some((Date::new(Some(year), digits(second, 1..=2)?, Some(digits(third, 1..=2)?))?, time));
+        }
+        let year = parse_year(third).filter(|_| third.len() == 4)?;
+        let (first, second) = (digits(first, 1..=2)?, digits(second, 1..=2)?);
+        let order = self.numeric_order.unwrap_or(match language {
+            Language::Eng => DateOrder::MonthDayYear,
+            _ => DateOrder::DayMonthYear,
+        });
+        let date = match order {
+            DateOrder::DayMonthYear => Date::new(Some(year), second, Some(first)),
+            DateOrder::MonthDayYear => Date::new(Some(year), first, Some(second)),
+        };
+        Some((date?, time))
+    }
+
+    // parses a date written with a month name, returning the number of words it spans.
+    fn parse_written_date(&self, words: &[String]) -> Option<(usize, Date)> {
+        let word = |index: usize| words.get(index).map(String::as_str).unwrap_or_default();
+        let skip_connector = |index: usize| if CONNECTORS.contains(&word(index)) { index + 1 } else { index };
+
+        // `12 March 2024`, `12th of March`
+        if let Some(day) = parse_day(word(0)) {
+            let index = skip_connector(1);
+            let month = self.parse_month(word(index))?;
+            let year_index = skip_connector(index + 1);
+            return match parse_year(word(year_index)) {
+                Some(year) => Some((year_index + 1, Date::new(Some(year), month, Some(day))?)),
+                None => Some((index + 1, Date::new(None, month, Some(day))?)),
+            };
+        }
+
+        // `March 12th, 2024`, `March 2024`
+        let month = self.parse_month(word(0))?;
+        if let Some(year) = parse_year(word(skip_connector(1))) {
+            return Some((skip_connector(1) + 1, Date::new(Some(year), month, None)?));
+        }
+        let day = parse_day(word(1))?;
+        match parse_year(word(2)) {
+            Some(year) => Some((3, Date::new(Some(year), month, Some(day))?)),
+            None => Some((2, Date::new(None, month, Some(day))?)),
+        }
+    }
+
+    /// Recognizes an expression at the start of the lowercased words, returning
+    /// the number of words it spans and its ISO-8601 form.
+    fn recognize(&self, words: &[String], language: Language) -> Option<(usize, String)> {
+        let (mut len, date, mut time) = match self.parse_numeric_date(&words[0], language) {
+            Some((date, time)) => (1, Some(date), time),
+            None => match self.parse_written_date(words) {
+                Some((len, date)) => (len, Some(date), None),
+                None => {
+                    let (len, time) = parse_time(words)?;
+                    (len, None, Some(time))
+                }
+            }
+        };
+
+        if let Some(date) = date {
+            if date.year.is_some() && words.get(len).is_some_and(|word| YEAR_SUFFIXES.contains(&word.as_str())) {
+                len += 1;
+            }
+            if time.is_none() && date.day.is_some() {
+                let index = if words.get(len).is_some_and(|word| TIME_CONNECTORS.contains(&word.as_str())) { len + 1 } else { len };
+                if let Some((time_len, parsed)) = words.get(index..).and_then(parse_time) {
+                    len = index + time_len;
+                    time = Some(parsed);
+                }
+            }
+        }
+
+        let mut value = String::new();
+        match date {
+            Some(Date { year: Some(year), month, day: Some(day) }) => value.push_str(&format!("{:04}-{:02}-{:02}", year, month, day)),
+            Some(Date { year: Some(year), month, day: None }) => value.push_str(&format!("{:04}-{:02}", year, month)),
+            Some(Date { year: None, month, day }) => value.push_str(&format!("--{:02}-{:02}", month, day.unwrap_or_default())),
+            None => {}
+        }
+        if let Some(Time { hour, minute, second }) = time {
+            if date.is_some() {
+                value.push('T');
+            }
+            value.push_str(&format!("{:02}:{:02}:{:02}", hour, minute, second));
+        }
+
+        Some((len, value))
+    }
+}
+
+pub struct DateRecognitionTokenStream<'token, T> {
+    filter: &'token DateRecognitionFilter,
+    // tokens pulled ahead while recognizing an expression.
+    window: VecDeque<OwnedToken<'token>>,
+    words: Vec<String>,
+    token: Option<OwnedToken<'token>>,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for DateRecognitionTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        if this.window.is_empty() {
+            polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                match this.tail.next() {
+                    None => polonius_return!(None),
+                    Some(token) if token.is_keyword() || !token.is_word() || !this.filter.may_start(token.text) => polonius_return!(Some(token)),
+                    Some(token) => this.window.push_back(token.to_owned()),
+                }
+            });
+        }
+
+        let front = &this.window[0];
+        if !front.is_keyword() && front.is_word() && this.filter.may_start(&front.text) {
+            while this.window.len() < MAX_WORDS {
+                match this.tail.next() {
+                    Some(token) => this.window.push_back(token.to_owned()),
+                    None => break,
+                }
+            }
+
+            this.words.clear();
+            this.words.extend(this.window
+                .iter()
+                .take_while(|token| !token.is_keyword() && token.is_word())
+                .map(|token| token.text.to_lowercase().trim_end_matches([',', ';']).to_string()));
+
+            if let Some((len, value)) = this.filter.recognize(&this.words, this.window[0].language()) {
+                let mut merged = this.window.pop_front()?;
+                for token in this.window.drain(..len - 1) {
+                    merged.text.push(' ');
+                    merged.text.push_str(&token.text);
+                    merged.offset_to = token.offset_to;
+                    merged.position_length = token.position + token.position_length - merged.position;
+                }
+                merged.attributes.insert(IsoDateTime(value));
+                this.token = Some(merged);
+                return this.token.as_mut().map(OwnedToken::borrowed);
+            }
+        }
+
+        this.token = this.window.pop_front();
+        this.token.as_mut().map(OwnedToken::borrowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::script::Script;
+    use crate::token::{SeparatorKind, TokenKind};
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn recognize(filter: &DateRecognitionFilter, text: &str, language: Language) -> Vec<(String, usize, usize, Option<String>)> {
+        let tokenizer = WhitespaceTokenizer {};
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, language, Script::Latin)));
+
+        stream.as_iter()
+            .map(|token| {
+                let value = token.attributes.get::<IsoDateTime>().map(|value| value.0.clone());
+                (token.text, token.position, token.position_length, value)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dates() {
+        let filter = DateRecognitionFilter { languages: vec![], numeric_order: None };
+
+        assert_eq!(recognize(&filter, "on March 12th, 2024 at 10:30 pm we met", Language::Eng), [
+            ("on".to_string(), 0, 1, None),
+            ("March 12th, 2024 at 10:30 pm".to_string(), 1, 6, Some("2024-03-12T22:30:00".to_string())),
+            ("we".to_string(), 7, 1, None),
+            ("met".to_string(), 8, 1, None),
+        ]);
+        assert_eq!(recognize(&filter, "12 марта 2024 г. в 12/03/2024", Language::Rus), [
+            ("12 марта 2024 г.".to_string(), 0, 4, Some("2024-03-12".to_string())),
+            ("в".to_string(), 4, 1, None),
+            ("12/03/2024".to_string(), 5, 1, Some("2024-03-12".to_string())),
+        ]);
+        assert_eq!(recognize(&filter, "12/03/2024 2024-02-30 May 2024 10:30", Language::Eng), [
+            ("12/03/2024".to_string(), 0, 1, Some("2024-12-03".to_string())),
+            ("2024-02-30".to_string(), 1, 1, None),
+            ("May 2024".to_string(), 2, 2, Some("2024-05".to_string())),
+            ("10:30".to_string(), 4, 1, Some("10:30:00".to_string())),
+        ]);
+    }
+
+    #[test]
+    fn configured_languages() {
+        let filter = DateRecognitionFilter { languages: vec![Language::Eng], numeric_order: Some(DateOrder::DayMonthYear) };
+
+        assert_eq!(recognize(&filter, "12 марта 12/03/2024", Language::Eng), [
+            ("12".to_string(), 0, 1, None),
+            ("марта".to_string(), 1, 1, None),
+            ("12/03/2024".to_string(), 2, 1, Some("2024-03-12".to_string())),
+        ]);
+    }
+
+    // turns the tokens with a `/` into separators, as a custom tokenizer may.
+    struct SlashSeparators<T>(T);
+
+    impl<'token, T: TokenStream<'token>> TokenStream<'token> for SlashSeparators<T> {
+        fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+            let mut token = self.0.next()?;
+            if token.text.contains('/') {
+                token.token_kind = TokenKind::Separator(SeparatorKind::Soft);
+            }
+            Some(token)
+        }
+    }
+
+    #[test]
+    fn separators() {
+        let filter = DateRecognitionFilter { languages: vec![], numeric_order: None };
+        let tokenizer = WhitespaceTokenizer {};
+        let stream = filter.apply(SlashSeparators(tokenizer.tokenize("12/03/2024 May 2024 10/30")));
+        let tokens: Vec<_> = stream.as_iter()
+            .map(|token| (token.text, token.attributes.get::<IsoDateTime>().map(|value| value.0.clone())))
+            .collect();
+        assert_eq!(tokens, [
+            ("12/03/2024".to_string(), None),
+            ("May 2024".to_string(), Some("2024-05".to_string())),
+            ("10/30".to_string(), None),
+        ]);
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use polonius_the_crab::{polonius, polonius_return};
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
+use schemars::schema::Schema;
+use serde_derive::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum EmojiMode {
+    /// Remove emoji.
+    Strip,
+    /// Split emoji from the words they are glued to.
+    Split,
+    /// Replace emoji with their short name.
+    Replace,
+    /// Keep emoji and emit their short name after them at the same position.
+    Inject,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EmojiConfig {
+    pub mode: EmojiMode,
+    /// CLDR annotation files of the language to take short names from, e.g.
+    /// `annotations/en.xml` and `annotationsDerived/en.xml`. Later files take precedence.
+    #[serde(default)]
+    pub annotations: Vec<PathBuf>,
+}
+
+/// Splits emoji, including ZWJ sequences, flags, keycaps and skin-tone
+/// modified ones, from the surrounding text and strips them, keeps them as
+/// separate tokens or replaces them with their CLDR short names, 🍕 becoming `pizza`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "EmojiConfig", into = "EmojiConfig")]
+pub struct EmojiFilter {
+    config: EmojiConfig,
+    names: Arc<HashMap<String, String>>,
+}
+
+impl EmojiFilter {
+    pub fn new(config: EmojiConfig) -> io::Result<Self> {
+        let mut names = HashMap::new();
+        for path in &config.annotations {
+            let annotations = fs::read_to_string(path)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+            parse_annotations(&annotations, &mut names);
+        }
+
+        Ok(EmojiFilter {
+            config,
+            names: Arc::new(names)
+        })
+    }
+
+    fn name(&self, emoji: &str) -> Option<&str> {
+        if let Some(name) = self.names.get(emoji) {
+            return Some(name);
+        }
+        // annotations list most emoji without the presentation selector and skin tones.
+        let base: String = emoji.chars().filter(|&c| c != '\u{FE0F}' && !is_skin_tone(c)).collect();
+        self.names.get(&base).map(String::as_str)
+    }
+}
+
+impl TryFrom<EmojiConfig> for EmojiFilter {
+    type Error = io::Error;
+
+    fn try_from(config: EmojiConfig) -> Result<Self, Self::Error> {
+        EmojiFilter::new(config)
+    }
+}
+
+impl From<EmojiFilter> for EmojiConfig {
+    fn from(filter: EmojiFilter) -> Self {
+        filter.config
+    }
+}
+
+impl JsonSchema for EmojiFilter {
+    fn schema_name() -> String {
+        "EmojiFilter".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        EmojiConfig::json_schema(gen)
+    }
+}
+
+// reads the text to speech names, `<annotation cp="🍕" type="tts">pizza</annotation>`.
+fn parse_annotations(xml: &str, names: &mut HashMap<String, String>) {
+    let mut rest = xml;
+    while let Some(start) = rest.find("<annotation ") {
+        rest = &rest[start + "<annotation ".len()..];
+        let Some(tag_end) = rest.find('>') else {
+            return;
+        };
+        let attributes = &rest[..tag_end];
+        rest = &rest[tag_end + 1..];
+        let Some(text_end) = rest.find("</annotation>") else {
+            return;
+        };
+        let text = &rest[..text_end];
+        rest = &rest[text_end..];
+
+        let is_tts = attributes.contains("type=\"tts\"");
+        let code_point = attributes
+            .split_once("cp=\"")
+            .and_then(|(_, value)| value.split_once('"'))
+            .map(|(value, _)| unescape(value));
+        if let (true, Some(code_point)) = (is_tts, code_point) {
+            names.insert(code_point, unescape(text.trim()));
+        }
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn is_skin_tone(c: char) -> bool {
+    matches!(c, '\u{1F3FB}'..='\u{1F3FF}')
+}
+
+// pictographic code points that are emoji without a presentation selector.
+fn is_pictographic(c: char) -> bool {
+    matches!(c,
+        '\u{231A}'..='\u{231B}' | '\u{23E9}'..='\u{23FA}' | '\u{24C2}' | '\u{25AA}'..='\u{25FE}'
+        | '\u{2600}'..='\u{27BF}' | '\u{2934}'..='\u{2935}' | '\u{2B05}'..='\u{2B55}' | '\u{3030}'
+        | '\u{303D}' | '\u{3297}' | '\u{3299}' | '\u{1F000}'..='\u{1FAFF}'
+    )
+}
+
+fn is_emoji(grapheme: &str) -> bool {
+    // keycaps and text symbols with an emoji presentation selector, `1️⃣`, `©️`.
+    grapheme.chars().any(|c| is_pictographic(c) || c == '\u{20E3}')
+        || grapheme.chars().nth(1) == Some('\u{FE0F}')
+}
+
+pub struct EmojiTokenStream<'token, T> {
+    filter: &'token EmojiFilter,
+    // parts of a token containing emoji.
+    queue: VecDeque<OwnedToken<'token>>,
+    token: Option<OwnedToken<'token>>,
+    // change of positions caused by split tokens.
+    shift: isize,
+    tail: T,
+}
+
+#[typetag::serde]
+impl TokenFilter for EmojiFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = EmojiTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        EmojiTokenStream {
+            tail: token_stream,
+            filter: self,
+            queue: VecDeque::new(),
+            token: None,
+            shift: 0,
+        }
+    }
+}
+
+// splits a token at emoji boundaries into the queue, returning the number of positions taken.
+fn split<'token>(filter: &EmojiFilter, queue: &mut VecDeque<OwnedToken<'token>>, token: &BorrowedToken<'_, 'token>) -> usize {
+    // offsets of the parts are only known if no previous filter changed the text.
+    let offsets_match = token.offset_to - token.offset_from == token.text.len();
+    let position = token.position;
+    let push = |queue: &mut VecDeque<OwnedToken<'token>>, text: &str, start: usize, position: usize| {
+        let mut part = token.to_owned();
+        part.text.clear();
+        part.text.push_str(text);
+        if offsets_match {
+            part.offset_from = token.offset_from + start;
+            part.offset_to = part.offset_from + text.len();
+        }
+        part.position = position;
+        queue.push_back(part);
+    };
+
+    let mut positions = position..;
+    let mut word_start = None;
+    for (start, grapheme) in token.text.grapheme_indices(true) {
+        if !is_emoji(grapheme) {
+            word_start.get_or_insert(start);
+            continue;
+        }
+        if let Some(word_start) = word_start.take() {
+            push(queue, &token.text[word_start..start], word_start, positions.next().unwrap());
+        }
+        match filter.config.mode {
+            EmojiMode::Strip => {}
+            EmojiMode::Split => push(queue, grapheme, start, positions.next().unwrap()),
+            EmojiMode::Replace => {
+                let text = filter.name(grapheme).unwrap_or(grapheme);
+                push(queue, text, start, positions.next().unwrap());
+                if let Some(part) = queue.back_mut().filter(|_| offsets_match) {
+                    part.offset_to = token.offset_from + start + grapheme.len();
+                }
+            }
+            EmojiMode::Inject => {
+                let position = positions.next().unwrap();
+                push(queue, grapheme, start, position);
+                if let Some(name) = filter.name(grapheme) {
+                    push(queue, name, start, position);
+                    if let Some(part) = queue.back_mut().filter(|_| offsets_match) {
+                        part.offset_to = token.offset_from + start + grapheme.len();
+                    }
+                }
+            }
+        }
+    }
+    if let Some(word_start) = word_start {
+        push(queue, &token.text[word_start..], word_start, positions.next().unwrap());
+    }
+
+    positions.next().unwrap() - position
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for EmojiTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        while this.queue.is_empty() {
+            polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                let Some(mut token) = this.tail.next() else {
+                    polonius_return!(None);
+                };
+                token.position = token.position.wrapping_add_signed(this.shift);
+                if token.text.is_ascii() || token.is_keyword() || !token.text.graphemes(true).any(is_emoji) {
+                    polonius_return!(Some(token));
+                }
+                let parts = split(this.filter, &mut this.queue, &token);
+                this.shift += parts.saturating_sub(1) as isize;
+            });
+        }
+
+        this.token = this.queue.pop_front();
+        this.token.as_mut().map(OwnedToken::borrowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn annotations() -> PathBuf {
+        PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/emoji/en.xml"))
+    }
+
+    fn apply(mode: EmojiMode, text: &str) -> Vec<(String, usize)> {
+        let filter = EmojiFilter::new(EmojiConfig { mode, annotations: vec![annotations()] }).unwrap();
+        let tokenizer = WhitespaceTokenizer {};
+        filter.apply(tokenizer.tokenize(text)).as_iter().map(|token| (token.text, token.position)).collect()
+    }
+
+    fn texts(tokens: Vec<(String, usize)>) -> Vec<String> {
+        tokens.into_iter().map(|(text, _)| text).collect()
+    }
+
+    #[test]
+    fn modes() {
+        let text = "love🍕 👍🏽 👨‍👩‍👧!";
+        assert_eq!(texts(apply(EmojiMode::Strip, text)), ["love", "!"]);
+        assert_eq!(texts(apply(EmojiMode::Split, text)), ["love", "🍕", "👍🏽", "👨‍👩‍👧", "!"]);
+        assert_eq!(texts(apply(EmojiMode::Replace, text)), ["love", "pizza", "thumbs up", "family: man, woman, girl", "!"]);
+    }
+
+    #[test]
+    fn inject() {
+        assert_eq!(apply(EmojiMode::Inject, "🍕pizzeria 1️⃣ & done"), [
+            ("🍕".to_string(), 0),
+            ("pizza".to_string(), 0),
+            ("pizzeria".to_string(), 1),
+            ("1️⃣".to_string(), 2),
+            ("&".to_string(), 3),
+            ("done".to_string(), 4),
+        ]);
+    }
+}
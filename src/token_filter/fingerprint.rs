@@ -0,0 +1,136 @@
+use std::collections::BTreeSet;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Collapses the stream into a single token made of the sorted, deduplicated
+/// words joined by `separator`, e.g. for near-duplicate detection.
+/// Nothing is emitted if the fingerprint is longer than `max_output_size` bytes.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FingerprintFilter {
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    #[serde(default = "default_max_output_size")]
+    pub max_output_size: usize,
+}
+
+impl Default for FingerprintFilter {
+    fn default() -> Self {
+        FingerprintFilter {
+            separator: default_separator(),
+            max_output_size: default_max_output_size(),
+        }
+    }
+}
+
+fn default_separator() -> String {
+    " ".to_string()
+}
+
+fn default_max_output_size() -> usize {
+    255
+}
+
+#[typetag::serde]
+impl TokenFilter for FingerprintFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = FingerprintTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        FingerprintTokenStream {
+            tail: token_stream,
+            filter: self,
+            token: None,
+            done: false,
+        }
+    }
+}
+
+pub struct FingerprintTokenStream<'token, T> {
+    filter: &'token FingerprintFilter,
+    token: Option<OwnedToken<'token>>,
+    done: bool,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for FingerprintTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        if std::mem::replace(&mut self.done, true) {
+            return None;
+        }
+
+        let mut words = BTreeSet::new();
+        while let Some(token) = self.tail.next() {
+            if !token.is_word() {
+                continue;
+            }
+            match &mut self.token {
+                Some(fingerprint) => fingerprint.offset_to = token.offset_to,
+                None => self.token = Some(token.to_owned()),
+            }
+            words.insert(token.text.clone());
+        }
+
+        let fingerprint = self.token.as_mut()?;
+        fingerprint.text.clear();
+        for word in words {
+            if !fingerprint.text.is_empty() {
+                fingerprint.text.push_str(&self.filter.separator);
+            }
+            fingerprint.text.push_str(&word);
+        }
+        if fingerprint.text.len() > self.filter.max_output_size {
+            return None;
+        }
+        fingerprint.position = 0;
+        fingerprint.position_length = 1;
+
+        Some(fingerprint.borrowed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::Analyzer;
+    use crate::analyzer::text_analyzer::TextAnalyzer;
+    use crate::language_detection::whichlang::WhichLangDetector;
+    use crate::token_filter::lower_case::LowerCaseFilter;
+    use crate::token_filter::token_filter_layer::{BaseLevel, TokenFilterLayers};
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn fingerprint() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = FingerprintFilter::default();
+        let tokens: Vec<(String, usize, usize)> = filter.apply(tokenizer.tokenize("the quick fox jumps over the fox"))
+            .as_iter()
+            .map(|token| (token.text, token.offset_from, token.offset_to))
+            .collect();
+        assert_eq!(tokens, [("fox jumps over quick the".to_string(), 0, 32)]);
+
+        let filter = FingerprintFilter { max_output_size: 10, ..filter };
+        assert_eq!(filter.apply(tokenizer.tokenize("the quick fox jumps")).as_iter().count(), 0);
+    }
+
+    #[test]
+    fn analyzer_fingerprint() {
+        let analyzer = TextAnalyzer {
+            character_filters: crate::char_filter::character_filter_layer::BaseLevel,
+            language_detector: WhichLangDetector::default(),
+            segment_character_filters: None,
+            tokenizer: WhitespaceTokenizer {},
+            token_filters: BaseLevel.wrap_layer(LowerCaseFilter {}).wrap_layer(FingerprintFilter::default()),
+        };
+        let text = "The Weather In London Is Nice Today Погода в Москве Today";
+
+        let tokens: Vec<(String, usize, usize)> = analyzer.analyze(text)
+            .as_iter()
+            .map(|token| (token.text, token.offset_from, token.offset_to))
+            .collect();
+        assert_eq!(tokens, [("in is london nice the today weather в москве погода".to_string(), 0, text.len())]);
+    }
+}
@@ -0,0 +1,133 @@
+use std::mem;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::script::Script;
+use crate::token::BorrowedToken;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Normalizes Hebrew script tokens: strips niqqud and cantillation marks,
+/// removes geresh and gershayim, expands Yiddish ligatures and optionally
+/// replaces final letter forms with the regular ones.
+///
+/// Only tokens whose script is [`Script::Hebrew`] are touched.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HebrewNormalizationFilter {
+    #[serde(default = "crate::token_filter::enabled")]
+    pub remove_niqqud: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub remove_cantillation: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub remove_geresh: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub expand_ligatures: bool,
+    #[serde(default)]
+    pub normalize_final_forms: bool,
+}
+
+impl Default for HebrewNormalizationFilter {
+    fn default() -> Self {
+        HebrewNormalizationFilter {
+            remove_niqqud: true,
+            remove_cantillation: true,
+            remove_geresh: true,
+            expand_ligatures: true,
+            normalize_final_forms: false,
+        }
+    }
+}
+
+#[typetag::serde]
+impl TokenFilter for HebrewNormalizationFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = HebrewNormalizationTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        HebrewNormalizationTokenStream {
+            tail: token_stream,
+            filter: self,
+            buffer: String::with_capacity(100)
+        }
+    }
+}
+
+fn is_niqqud(c: char) -> bool {
+    matches!(c, '\u{05B0}'..='\u{05BD}' | '\u{05BF}' | '\u{05C1}' | '\u{05C2}' | '\u{05C4}' | '\u{05C5}' | '\u{05C7}')
+}
+
+fn is_cantillation(c: char) -> bool {
+    matches!(c, '\u{0591}'..='\u{05AF}')
+}
+
+impl HebrewNormalizationFilter {
+    fn normalize(&self, text: &str, output: &mut String) {
+        output.clear();
+        for c in text.chars() {
+            let c = match c {
+                c if self.remove_niqqud && is_niqqud(c) => continue,
+                c if self.remove_cantillation && is_cantillation(c) => continue,
+                '\u{05F3}' | '\u{05F4}' if self.remove_geresh => continue,
+                '\u{05F0}' if self.expand_ligatures => {
+                    output.push_str("\u{05D5}\u{05D5}");
+                    continue
+                }
+                '\u{05F1}' if self.expand_ligatures => {
+                    output.push_str("\u{05D5}\u{05D9}");
+                    continue
+                }
+                '\u{05F2}' if self.expand_ligatures => {
+                    output.push_str("\u{05D9}\u{05D9}");
+                    continue
+                }
+                '\u{05DA}' if self.normalize_final_forms => '\u{05DB}',
+                '\u{05DD}' if self.normalize_final_forms => '\u{05DE}',
+                '\u{05DF}' if self.normalize_final_forms => '\u{05E0}',
+                '\u{05E3}' if self.normalize_final_forms => '\u{05E4}',
+                '\u{05E5}' if self.normalize_final_forms => '\u{05E6}',
+                c => c
+            };
+            output.push(c);
+        }
+    }
+}
+
+pub struct HebrewNormalizationTokenStream<'token, T> {
+    buffer: String,
+    filter: &'token HebrewNormalizationFilter,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for HebrewNormalizationTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let token = self.tail.next()?;
+        if !token.is_keyword() && !token.text.is_ascii() && token.script() == Script::Hebrew {
+            self.filter.normalize(token.text, &mut self.buffer);
+            mem::swap(token.text, &mut self.buffer);
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::Language;
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn hebrew_normalization() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = HebrewNormalizationFilter {
+            normalize_final_forms: true,
+            ..Default::default()
+        };
+        let text = "שָׁלוֹם צה״ל ײִדיש";
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Heb, Script::Hebrew)));
+
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["שלומ", "צהל", "יידיש"]);
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use polonius_the_crab::{polonius, polonius_return};
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
+use schemars::schema::Schema;
+use serde_derive::{Deserialize, Serialize};
+use crate::language::Language;
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HyphenationConfig {
+    /// TeX hyphenation files per language, either `hyph-*.tex` files with
+    /// `\patterns{}` and `\hyphenation{}` blocks or plain `hyph-*.pat.txt` pattern lists.
+    pub patterns: BTreeMap<Language, Vec<PathBuf>>,
+    /// Minimum length of a fragment in characters.
+    #[serde(default = "default_min_fragment_length")]
+    pub min_fragment_length: usize,
+    /// Emit the whole word before its fragments.
+    #[serde(default = "crate::token_filter::enabled")]
+    pub preserve_original: bool,
+}
+
+fn default_min_fragment_length() -> usize {
+    2
+}
+
+/// Splits words at their hyphenation points using Liang's algorithm with the
+/// TeX patterns of the token language, emitting the fragments at the position
+/// of the word, e.g. `kirjastotalo` into `kir`, `jas`, `to`, `ta`, `lo`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "HyphenationConfig", into = "HyphenationConfig")]
+pub struct HyphenationFilter {
+    config: HyphenationConfig,
+    hyphenators: Arc<HashMap<Language, Hyphenator>>,
+}
+
+impl HyphenationFilter {
+    pub fn new(config: HyphenationConfig) -> io::Result<Self> {
+        let mut hyphenators = HashMap::new();
+        for (&language, paths) in &config.patterns {
+            let hyphenator = hyphenators.entry(language).or_insert_with(Hyphenator::default);
+            for path in paths {
+                let patterns = fs::read_to_string(path)
+                    .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+                hyphenator.load(&patterns);
+            }
+        }
+
+        Ok(HyphenationFilter {
+            config,
+            hyphenators: Arc::new(hyphenators),
+        })
+    }
+}
+
+impl TryFrom<HyphenationConfig> for HyphenationFilter {
+    type Error = io::Error;
+
+    fn try_from(config: HyphenationConfig) -> Result<Self, Self::Error> {
+        HyphenationFilter::new(config)
+    }
+}
+
+impl From<HyphenationFilter> for HyphenationConfig {
+    fn from(filter: HyphenationFilter) -> Self {
+        filter.config
+    }
+}
+
+impl JsonSchema for HyphenationFilter {
+    fn schema_name() -> String {
+        "HyphenationFilter".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        HyphenationConfig::json_schema(gen)
+    }
+}
+
+#[derive(Default)]
+struct Hyphenator {
+    // letters of a pattern to the values of the gaps around them, `1ja` is `ja` => [1, 0, 0].
+    patterns: HashMap<String, Vec<u8>>,
+    max_pattern_length: usize,
+    // words hyphenated explicitly, to the character indices of their breaks.
+    exceptions: HashMap<String, Vec<usize>>,
+}
+
+impl Hyphenator {
+    fn load(&mut self, text: &str) {
+        let text: String = text.lines().map(|line| line.split('%').next().unwrap_or_default()).collect::<Vec<_>>().join("\n");
+        let block = |name: &str| {
+            text.split_once(name)
+                .map(|(_, rest)| rest.trim_start().trim_start_matches('{'))
+                .map(|rest| rest.split('}').next().unwrap_or_default())
+        };
+
+        match block("\\patterns") {
+            Some(patterns) => patterns.split_whitespace().for_each(|pattern| self.add_pattern(pattern)),
+            None => text.split_whitespace().for_each(|pattern| self.add_pattern(pattern)),
+        }
+        if let Some(exceptions) = block("\\hyphenation") {
+            exceptions.split_whitespace().for_each(|exception| self.add_exception(exception));
+        }
+    }
+
+    fn add_pattern(&mut self, pattern: &str) {
+        let mut letters = String::new();
+        let mut values = Vec::new();
+        let mut value = 0;
+        for c in pattern.chars() {
+            match c.to_digit(10) {
+                Some(digit) => value = digit as u8,
+                None => {
+                    letters.push(c);
+                    values.push(std::mem::take(&mut value));
+                }
+            }
+        }
+        values.push(value);
+        self.max_pattern_length = self.max_pattern_length.max(values.len() - 1);
+        self.patterns.insert(letters, values);
+    }
+
+    fn add_exception(&mut self, exception: &str) {
+        let mut word = String::new();
+        let mut breaks = Vec::new();
+        for c in exception.chars() {
+            if c == '-' {
+                breaks.push(word.chars().count());
+            } else {
+                word.push(c);
+            }
+        }
+        self.exceptions.insert(word, breaks);
+    }
+
+    /// Character indices the word can be broken before.
+    fn breaks(&self, word: &str) -> Vec<usize> {
+        let lowercase: String = word.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+        if let Some(breaks) = self.exceptions.get(&lowercase) {
+            return breaks.clone();
+        }
+
+        let dotted = format!(".{}.", lowercase);
+        let boundaries: Vec<usize> = dotted.char_indices().map(|(index, _)| index).chain([dotted.len()]).collect();
+        let length = boundaries.len() - 1;
+        let mut points = vec![0; length + 1];
+        for start in 0..length {
+            for end in start + 1..=length.min(start + self.max_pattern_length) {
+                if let Some(values) = self.patterns.get(&dotted[boundaries[start]..boundaries[end]]) {
+                    for (point, &value) in points[start..].iter_mut().zip(values) {
+                        *point = (*point).max(value);
+                    }
+                }
+            }
+        }
+
+        // the gap before a character of the word is after the leading dot.
+        (1..length - 2).filter(|&index| points[index + 1] % 2 == 1).collect()
+    }
+}
+
+#[typetag::serde]
+impl TokenFilter for HyphenationFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = HyphenationTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        HyphenationTokenStream {
+            tail: token_stream,
+            filter: self,
+            queue: VecDeque::new(),
+            token: None,
+        }
+    }
+}
+
+pub struct HyphenationTokenStream<'token, T> {
+    filter: &'token HyphenationFilter,
+    // the word and its fragments.
+    queue: VecDeque<OwnedToken<'token>>,
+    token: Option<OwnedToken<'token>>,
+    tail: T,
+}
+
+// splits a token at its hyphenation points into the queue.
+fn split<'token>(filter: &HyphenationFilter, hyphenator: &Hyphenator, queue: &mut VecDeque<OwnedToken<'token>>, token: &BorrowedToken<'_, 'token>) {
+    let min_fragment_length = filter.config.min_fragment_length.max(1);
+    let boundaries: Vec<usize> = token.text.char_indices().map(|(index, _)| index).chain([token.text.len()]).collect();
+    let length = boundaries.len() - 1;
+
+    let mut fragments = vec![0];
+    for index in hyphenator.breaks(token.text) {
+        if index - fragments[fragments.len() - 1] >= min_fragment_length && length - index >= min_fragment_length {
+            fragments.push(index);
+        }
+    }
+    if fragments.len() == 1 {
+        return;
+    }
+    fragments.push(length);
+
+    // offsets of the fragments are only known if no previous filter changed the text.
+    let offsets_match = token.offset_to - token.offset_from == token.text.len();
+    if filter.config.preserve_original {
+        queue.push_back(token.to_owned());
+    }
+    for fragment in fragments.windows(2) {
+        let (start, end) = (boundaries[fragment[0]], boundaries[fragment[1]]);
+        let mut part = token.to_owned();
+        part.text.clear();
+        part.text.push_str(&token.text[start..end]);
+        if offsets_match {
+            part.offset_from = token.offset_from + start;
+            part.offset_to = token.offset_from + end;
+        }
+        queue.push_back(part);
+    }
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for HyphenationTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        while this.queue.is_empty() {
+            polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                let Some(token) = this.tail.next() else {
+                    polonius_return!(None);
+                };
+                if !token.is_word() || token.is_keyword() {
+                    polonius_return!(Some(token));
+                }
+                let Some(hyphenator) = this.filter.hyphenators.get(&token.language()) else {
+                    polonius_return!(Some(token));
+                };
+                split(this.filter, hyphenator, &mut this.queue, &token);
+                if this.queue.is_empty() {
+                    polonius_return!(Some(token));
+                }
+            });
+        }
+
+        this.token = this.queue.pop_front();
+        this.token.as_mut().map(OwnedToken::borrowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::script::Script;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn patterns() -> PathBuf {
+        PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hyphenation/hyph-fi.tex"))
+    }
+
+    fn apply(filter: &HyphenationFilter, text: &str, language: Language) -> Vec<(String, usize, usize, usize)> {
+        let tokenizer = WhitespaceTokenizer {};
+        filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, language, Script::Latin)))
+            .as_iter()
+            .map(|token| (token.text, token.position, token.offset_from, token.offset_to))
+            .collect()
+    }
+
+    #[test]
+    fn hyphenation() {
+        let filter = HyphenationFilter::new(HyphenationConfig {
+            patterns: BTreeMap::from([(Language::Fin, vec![patterns()])]),
+            min_fragment_length: 2,
+            preserve_original: true,
+        }).unwrap();
+
+        assert_eq!(apply(&filter, "iso kirjastotalo", Language::Fin), [
+            ("iso".to_string(), 0, 0, 3),
+            ("kirjastotalo".to_string(), 1, 4, 16),
+            ("kir".to_string(), 1, 4, 7),
+            ("jas".to_string(), 1, 7, 10),
+            ("to".to_string(), 1, 10, 12),
+            ("ta".to_string(), 1, 12, 14),
+            ("lo".to_string(), 1, 14, 16),
+        ]);
+        assert_eq!(apply(&filter, "Kahvila", Language::Fin), [
+            ("Kahvila".to_string(), 0, 0, 7),
+            ("Kah".to_string(), 0, 0, 3),
+            ("vi".to_string(), 0, 3, 5),
+            ("la".to_string(), 0, 5, 7),
+        ]);
+        assert_eq!(apply(&filter, "kirjastotalo", Language::Eng), [("kirjastotalo".to_string(), 0, 0, 12)]);
+
+        let filter = HyphenationFilter::new(HyphenationConfig {
+            min_fragment_length: 3,
+            preserve_original: false,
+            ..filter.config.clone()
+        }).unwrap();
+        assert_eq!(apply(&filter, "kirjastotalo", Language::Fin), [
+            ("kir".to_string(), 0, 0, 3),
+            ("jas".to_string(), 0, 3, 6),
+            ("totalo".to_string(), 0, 6, 12),
+        ]);
+    }
+}
@@ -0,0 +1,144 @@
+use std::mem;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::token_filter::cjk_width::is_cjk_script;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum KanaConversion {
+    HiraganaToKatakana,
+    KatakanaToHiragana,
+}
+
+/// Converts between hiragana and katakana. With `keep_original` the
+/// converted form is emitted after the original token at the same position.
+///
+/// Only tokens whose script is Han, Hiragana, Katakana or Hangul are touched.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KanaConversionFilter {
+    pub conversion: KanaConversion,
+    #[serde(default)]
+    pub keep_original: bool,
+}
+
+#[typetag::serde]
+impl TokenFilter for KanaConversionFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = KanaConversionTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        KanaConversionTokenStream {
+            tail: token_stream,
+            filter: self,
+            buffer: String::with_capacity(100),
+            token: None,
+            pending: false,
+        }
+    }
+}
+
+// hiragana and katakana blocks are laid out identically, 0x60 code points apart.
+const KANA_OFFSET: u32 = 0x60;
+
+impl KanaConversion {
+    fn convert_char(self, c: char) -> char {
+        let code = c as u32;
+        let converted = match self {
+            KanaConversion::HiraganaToKatakana => match code {
+                0x3041..=0x3096 | 0x309D..=0x309E => code + KANA_OFFSET,
+                _ => code
+            },
+            KanaConversion::KatakanaToHiragana => match code {
+                0x30A1..=0x30F6 | 0x30FD..=0x30FE => code - KANA_OFFSET,
+                _ => code
+            },
+        };
+        char::from_u32(converted).unwrap_or(c)
+    }
+
+    // writes the converted text into output and returns whether anything changed.
+    fn convert(self, text: &str, output: &mut String) -> bool {
+        output.clear();
+        let mut changed = false;
+        for c in text.chars() {
+            let converted = self.convert_char(c);
+            changed |= converted != c;
+            output.push(converted);
+        }
+        changed
+    }
+}
+
+pub struct KanaConversionTokenStream<'token, T> {
+    buffer: String,
+    filter: &'token KanaConversionFilter,
+    token: Option<OwnedToken<'token>>,
+    pending: bool,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for KanaConversionTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        if mem::take(&mut self.pending) {
+            return self.token.as_mut().map(OwnedToken::borrowed);
+        }
+
+        let token = self.tail.next()?;
+        if token.is_keyword()
+            || token.text.is_ascii()
+            || !is_cjk_script(token.script())
+            || !self.filter.conversion.convert(token.text, &mut self.buffer) {
+            return Some(token);
+        }
+
+        if self.filter.keep_original {
+            let converted = token.clone_into_slot(&mut self.token);
+            mem::swap(&mut converted.text, &mut self.buffer);
+            self.pending = true;
+        } else {
+            mem::swap(token.text, &mut self.buffer);
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::Language;
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::script::Script;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn kana_conversion() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = KanaConversionFilter {
+            conversion: KanaConversion::KatakanaToHiragana,
+            keep_original: false,
+        };
+        let text = "カタカナ ひらがな 漢字";
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Jpn, Script::Katakana)));
+
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["かたかな", "ひらがな", "漢字"]);
+    }
+
+    #[test]
+    fn kana_conversion_keep_original() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = KanaConversionFilter {
+            conversion: KanaConversion::HiraganaToKatakana,
+            keep_original: true,
+        };
+        let text = "ひらがな 漢字";
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Jpn, Script::Hiragana)));
+
+        let tokens: Vec<(String, usize)> = stream.as_iter().map(|token| (token.text, token.position)).collect();
+        assert_eq!(tokens, [("ひらがな".to_string(), 0), ("ヒラガナ".to_string(), 0), ("漢字".to_string(), 1)]);
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::{BTreeSet, HashSet};
+use regex::Regex;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
+use schemars::schema::Schema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::BorrowedToken;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeywordMarkerConfig {
+    #[serde(default)]
+    pub keywords: BTreeSet<String>,
+    #[serde(default, with = "serde_regex")]
+    #[schemars(with = "Option<String>")]
+    pub pattern: Option<Regex>,
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+
+/// Marks tokens found in a word list or matching a pattern as [`Keyword`](crate::token::Keyword)s,
+/// so that the following modifying filters leave them alone.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "KeywordMarkerConfig", into = "KeywordMarkerConfig")]
+pub struct KeywordMarkerFilter {
+    config: KeywordMarkerConfig,
+    keywords: HashSet<String>,
+}
+
+impl KeywordMarkerFilter {
+    pub fn new(config: KeywordMarkerConfig) -> Self {
+        let keywords = config.keywords
+            .iter()
+            .map(|keyword| if config.ignore_case { keyword.to_lowercase() } else { keyword.clone() })
+            .collect();
+
+        KeywordMarkerFilter {
+            config,
+            keywords
+        }
+    }
+
+    fn is_keyword(&self, text: &str, buffer: &mut String) -> bool {
+        let in_keywords = if self.config.ignore_case && !self.keywords.is_empty() {
+            buffer.clear();
+            buffer.extend(text.chars().flat_map(char::to_lowercase));
+            self.keywords.contains(buffer.as_str())
+        } else {
+            self.keywords.contains(text)
+        };
+
+        in_keywords || self.config.pattern.as_ref().is_some_and(|pattern| pattern.is_match(text))
+    }
+}
+
+impl From<KeywordMarkerConfig> for KeywordMarkerFilter {
+    fn from(config: KeywordMarkerConfig) -> Self {
+        KeywordMarkerFilter::new(config)
+    }
+}
+
+impl From<KeywordMarkerFilter> for KeywordMarkerConfig {
+    fn from(filter: KeywordMarkerFilter) -> Self {
+        filter.config
+    }
+}
+
+impl JsonSchema for KeywordMarkerFilter {
+    fn schema_name() -> String {
+        "KeywordMarkerFilter".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        KeywordMarkerConfig::json_schema(gen)
+    }
+}
+
+#[typetag::serde]
+impl TokenFilter for KeywordMarkerFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = KeywordMarkerTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        KeywordMarkerTokenStream {
+            tail: token_stream,
+            filter: self,
+            buffer: String::with_capacity(100)
+        }
+    }
+}
+
+pub struct KeywordMarkerTokenStream<'token, T> {
+    buffer: String,
+    filter: &'token KeywordMarkerFilter,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for KeywordMarkerTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut token = self.tail.next()?;
+        if !token.is_keyword() && self.filter.is_keyword(token.text, &mut self.buffer) {
+            token.set_keyword(true);
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::token_filter::lower_case::LowerCaseFilter;
+    use crate::token_filter::token_filter_layer::{BaseLevel, TokenFilterLayers};
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn keyword_marker() {
+        let marker = KeywordMarkerFilter::new(KeywordMarkerConfig {
+            keywords: BTreeSet::from(["iPhone".to_string()]),
+            pattern: Some(Regex::new(r"^SKU-\d+$").unwrap()),
+            ignore_case: true,
+        });
+        let layers = BaseLevel
+            .wrap_layer(marker)
+            .wrap_layer(LowerCaseFilter {});
+        let tokenizer = WhitespaceTokenizer {};
+        let stream = layers.apply_layer(tokenizer.tokenize("New IPHONE SKU-1234 SKU-X"));
+
+        let tokens: Vec<(String, bool)> = stream.as_iter().map(|token| {
+            let keyword = token.is_keyword();
+            (token.text, keyword)
+        }).collect();
+        assert_eq!(tokens, [
+            ("new".to_string(), false),
+            ("IPHONE".to_string(), true),
+            ("SKU-1234".to_string(), true),
+            ("sku-x".to_string(), false),
+        ]);
+    }
+
+    #[test]
+    fn serialize() {
+        let serialized = r#"{"keywords":["Rust"],"pattern":"^v\\d+$","ignore_case":false}"#;
+        let filter: KeywordMarkerFilter = serde_json::from_str(serialized).unwrap();
+        assert_eq!(serde_json::to_string(&filter).unwrap(), serialized);
+    }
+}
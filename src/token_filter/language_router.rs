@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use polonius_the_crab::{polonius, polonius_return};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::inline_dyn::Dynamic;
+use crate::language::Language;
+use crate::token::BorrowedToken;
+use crate::token_filter::token_channel::TokenChannel;
+use crate::token_filter::token_filter_layer::{BoxTokenFilterLayer, TokenFilterLayers};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Sends every token through the filter chain registered for its language.
+/// Tokens of languages without a chain, or detected with a confidence below
+/// `min_confidence`, pass through untouched.
+///
+/// Like with [`ConditionalFilter`](crate::token_filter::conditional::ConditionalFilter)
+/// the chains see the routed tokens one at a time.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LanguageRouterFilter {
+    pub routes: BTreeMap<Language, BoxTokenFilterLayer>,
+    #[serde(default)]
+    pub min_confidence: f64,
+}
+
+#[typetag::serde]
+impl TokenFilter for LanguageRouterFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = LanguageRouterTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        LanguageRouterTokenStream {
+            tail: token_stream,
+            filter: self,
+            channel: TokenChannel::new(),
+            route: None,
+        }
+    }
+}
+
+pub struct LanguageRouterTokenStream<'token, T> {
+    filter: &'token LanguageRouterFilter,
+    channel: TokenChannel<'token>,
+    // the chain applied to the last routed token, until drained.
+    route: Option<Dynamic<dyn TokenStream<'token> + 'token>>,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for LanguageRouterTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        loop {
+            if this.route.is_some() {
+                polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                    if let Some(token) = this.route.as_mut().and_then(TokenStream::next) {
+                        polonius_return!(Some(token));
+                    }
+                });
+                this.route = None;
+            }
+
+            let filters = polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                match this.tail.next() {
+                    None => polonius_return!(None),
+                    Some(token) => {
+                        let filter = this.filter;
+                        match filter.routes.get(&token.language()) {
+                            Some(filters) if token.language_confidence() >= filter.min_confidence => {
+                                this.channel.send(&token);
+                                filters
+                            }
+                            _ => polonius_return!(Some(token)),
+                        }
+                    }
+                }
+            });
+            this.route = Some(filters.apply_layer(this.channel.receiver()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::Analyzer;
+    use crate::analyzer::text_analyzer::TextAnalyzer;
+    use crate::language_detection::whatlang::WhatLangDetector;
+    use crate::language_detection::whichlang::WhichLangDetector;
+    use crate::token_filter::lower_case::LowerCaseFilter;
+    use crate::token_filter::token_filter_layer::BaseLevel;
+    use crate::token_filter::transliteration::{BuiltInRules, TransliterationConfig, TransliterationDirection, TransliterationFilter, TransliterationRules};
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn router() -> LanguageRouterFilter {
+        let transliteration = TransliterationFilter::new(TransliterationConfig {
+            rules: TransliterationRules::BuiltIn(BuiltInRules::CyrillicLatinBgnPcgn),
+            direction: TransliterationDirection::Forward,
+            inject: false,
+        }).unwrap();
+
+        LanguageRouterFilter {
+            routes: BTreeMap::from([
+                (Language::Eng, BaseLevel.wrap_dynamic_layer(LowerCaseFilter {})),
+                (Language::Rus, BaseLevel.wrap_dynamic_layer(transliteration)),
+            ]),
+            min_confidence: 0.0,
+        }
+    }
+
+    #[test]
+    fn language_router() {
+        let analyzer = TextAnalyzer {
+            character_filters: crate::char_filter::character_filter_layer::BaseLevel,
+            language_detector: WhichLangDetector::default(),
+            segment_character_filters: None,
+            tokenizer: WhitespaceTokenizer {},
+            token_filters: BaseLevel.wrap_layer(router()),
+        };
+        let text = "The Weather In London Is Nice Today Погода в Москве";
+
+        let tokens: Vec<String> = analyzer.analyze(text).as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["the", "weather", "in", "london", "is", "nice", "today", "Pogoda", "v", "Moskve"]);
+    }
+
+    #[test]
+    fn min_confidence() {
+        let analyzer = TextAnalyzer {
+            character_filters: crate::char_filter::character_filter_layer::BaseLevel,
+            language_detector: WhatLangDetector::default(),
+            segment_character_filters: None,
+            tokenizer: WhitespaceTokenizer {},
+            token_filters: BaseLevel.wrap_layer(LanguageRouterFilter { min_confidence: 0.9, ..router() }),
+        };
+
+        let analyze = |text| analyzer.analyze(text).as_iter().map(|token| token.text).collect::<Vec<String>>();
+
+        assert_eq!(analyze("The Weather In London"), ["The", "Weather", "In", "London"]);
+        assert_eq!(analyze("The Sun Is Shining Over The River Thames In London Today"), ["the", "sun", "is", "shining", "over", "the", "river", "thames", "in", "london", "today"]);
+    }
+
+    #[test]
+    fn serialize() {
+        let serialized = serde_json::to_string(&router()).unwrap();
+        let deserialized: LanguageRouterFilter = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+    }
+}
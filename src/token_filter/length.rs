@@ -0,0 +1,79 @@
+use polonius_the_crab::{polonius, polonius_return};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::BorrowedToken;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Removes tokens whose length in characters is outside of `min..=max`.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LengthFilter {
+    #[serde(default)]
+    pub min: usize,
+    #[serde(default)]
+    pub max: Option<usize>,
+}
+
+impl LengthFilter {
+    fn accepts(&self, text: &str) -> bool {
+        // byte length bounds the char count from above, so most tokens need no counting.
+        if text.len() < self.min {
+            return false;
+        }
+        if self.max.is_none_or(|max| text.len() <= max) && self.min == 0 {
+            return true;
+        }
+        let length = text.chars().count();
+        length >= self.min && self.max.is_none_or(|max| length <= max)
+    }
+}
+
+#[typetag::serde]
+impl TokenFilter for LengthFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = LengthTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        LengthTokenStream {
+            tail: token_stream,
+            filter: self,
+        }
+    }
+}
+
+pub struct LengthTokenStream<'token, T> {
+    filter: &'token LengthFilter,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for LengthTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        loop {
+            polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                match this.tail.next() {
+                    None => polonius_return!(None),
+                    Some(token) if this.filter.accepts(token.text) => polonius_return!(Some(token)),
+                    Some(_) => {}
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn length() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = LengthFilter { min: 2, max: Some(4) };
+        let stream = filter.apply(tokenizer.tokenize("a ab abcd abcde ёжик ёжики"));
+
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["ab", "abcd", "ёжик"]);
+    }
+}
@@ -0,0 +1,108 @@
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::BorrowedToken;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Ends the stream after `max_token_count` tokens.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LimitTokenCountFilter {
+    pub max_token_count: usize,
+    /// Keep pulling the remaining tokens from the previous filters once the limit is reached,
+    /// for filters that rely on seeing the whole input.
+    #[serde(default)]
+    pub consume_all_tokens: bool,
+}
+
+#[typetag::serde]
+impl TokenFilter for LimitTokenCountFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = LimitTokenCountTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        LimitTokenCountTokenStream {
+            tail: token_stream,
+            filter: self,
+            count: 0,
+        }
+    }
+}
+
+pub struct LimitTokenCountTokenStream<'token, T> {
+    filter: &'token LimitTokenCountFilter,
+    count: usize,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for LimitTokenCountTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        if self.count < self.filter.max_token_count {
+            let token = self.tail.next();
+            if token.is_some() {
+                self.count += 1;
+            }
+            return token;
+        }
+        if self.filter.consume_all_tokens {
+            while self.tail.next().is_some() {}
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use crate::token_filter::token_channel::TokenChannel;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    // counts the tokens pulled from the tokenizer.
+    struct CountingTokenStream<T> {
+        tail: T,
+        pulled: Rc<Cell<usize>>,
+    }
+
+    impl<'token, T: TokenStream<'token>> TokenStream<'token> for CountingTokenStream<T> {
+        fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+            let token = self.tail.next();
+            if token.is_some() {
+                self.pulled.set(self.pulled.get() + 1);
+            }
+            token
+        }
+    }
+
+    fn limit(filter: &LimitTokenCountFilter, text: &str) -> (Vec<String>, usize) {
+        let tokenizer = WhitespaceTokenizer {};
+        let pulled = Rc::new(Cell::new(0));
+        let stream = filter.apply(CountingTokenStream { tail: tokenizer.tokenize(text), pulled: Rc::clone(&pulled) });
+        let tokens = stream.as_iter().map(|token| token.text).collect();
+        (tokens, pulled.get())
+    }
+
+    #[test]
+    fn limit_token_count() {
+        let filter = LimitTokenCountFilter { max_token_count: 2, consume_all_tokens: true };
+        assert_eq!(limit(&filter, "one two three four"), (vec!["one".to_string(), "two".to_string()], 4));
+
+        let filter = LimitTokenCountFilter { consume_all_tokens: false, ..filter };
+        assert_eq!(limit(&filter, "one two three four"), (vec!["one".to_string(), "two".to_string()], 2));
+        assert_eq!(limit(&filter, "one"), (vec!["one".to_string()], 1));
+
+        // a stream with no token yet, e.g. a channel, does not use up the limit.
+        let filter = LimitTokenCountFilter { max_token_count: 1, ..filter };
+        let tokenizer = WhitespaceTokenizer {};
+        let mut tokens = tokenizer.tokenize("one two");
+        let channel = TokenChannel::new();
+        let mut stream = filter.apply(channel.receiver());
+        assert!(stream.next().is_none());
+        channel.send(&tokens.next().unwrap());
+        assert_eq!(stream.next().map(|token| token.text.to_string()), Some("one".to_string()));
+        channel.send(&tokens.next().unwrap());
+        assert!(stream.next().is_none());
+    }
+}
@@ -43,6 +43,9 @@ impl<'token, T: TokenStream<'token>> TokenStream<'token> for LowerCaseTokenStrea
     fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
 
         if let Some(mut token) = self.tail.next() {
+            if token.is_keyword() {
+                return Some(token)
+            }
             if token.text.is_ascii() {
                 // fast track for ascii.
                 token.text.make_ascii_lowercase();
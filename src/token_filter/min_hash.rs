@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::token_filter::{enabled, TokenFilter};
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Collapses the stream into MinHash signature tokens for near-duplicate
+/// detection. Shingles of `shingle_size` consecutive words are hashed with
+/// `hash_count` hash functions, each split into `bucket_count` buckets keeping
+/// the `hash_set_size` smallest values.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MinHashFilter {
+    #[serde(default = "default_hash_count")]
+    pub hash_count: usize,
+    #[serde(default = "default_bucket_count")]
+    pub bucket_count: usize,
+    #[serde(default = "default_hash_set_size")]
+    pub hash_set_size: usize,
+    /// Fill empty buckets with the values of the next non-empty one.
+    #[serde(default = "crate::token_filter::enabled")]
+    pub with_rotation: bool,
+    #[serde(default = "default_shingle_size")]
+    pub shingle_size: usize,
+}
+
+impl Default for MinHashFilter {
+    fn default() -> Self {
+        MinHashFilter {
+            hash_count: default_hash_count(),
+            bucket_count: default_bucket_count(),
+            hash_set_size: default_hash_set_size(),
+            with_rotation: enabled(),
+            shingle_size: default_shingle_size(),
+        }
+    }
+}
+
+fn default_hash_count() -> usize {
+    1
+}
+
+fn default_bucket_count() -> usize {
+    512
+}
+
+fn default_hash_set_size() -> usize {
+    1
+}
+
+fn default_shingle_size() -> usize {
+    1
+}
+
+impl MinHashFilter {
+    fn signature(&self, shingles: &[u64]) -> Vec<String> {
+        let bucket_count = self.bucket_count.max(1);
+        let hash_set_size = self.hash_set_size.max(1);
+        let mut signature = Vec::new();
+        for hash in 0..self.hash_count {
+            let seed = mix(hash as u64 ^ 0x9E37_79B9_7F4A_7C15);
+            // smallest values of each bucket, sorted.
+            let mut buckets = vec![Vec::with_capacity(hash_set_size); bucket_count];
+            for &shingle in shingles {
+                let value = mix(shingle ^ seed);
+                let bucket = &mut buckets[((value as u128 * bucket_count as u128) >> 64) as usize];
+                if let Err(index) = bucket.binary_search(&value) {
+                    if index < hash_set_size {
+                        bucket.insert(index, value);
+                        bucket.truncate(hash_set_size);
+                    }
+                }
+            }
+
+            for index in 0..bucket_count {
+                let values = if self.with_rotation {
+                    match (index..bucket_count).chain(0..index).find(|&index| !buckets[index].is_empty()) {
+                        Some(index) => &buckets[index],
+                        None => continue,
+                    }
+                } else {
+                    &buckets[index]
+                };
+                for value in values {
+                    signature.push(format!("{}_{}_{:016x}", hash, index, value));
+                }
+            }
+        }
+
+        signature
+    }
+}
+
+// FNV-1a, stable across runs and platforms unlike the std hasher.
+fn hash(text: &str) -> u64 {
+    text.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3))
+}
+
+// splitmix64 finalizer.
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+#[typetag::serde]
+impl TokenFilter for MinHashFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = MinHashTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        MinHashTokenStream {
+            tail: token_stream,
+            filter: self,
+            signature: VecDeque::new(),
+            token: None,
+            done: false,
+        }
+    }
+}
+
+pub struct MinHashTokenStream<'token, T> {
+    filter: &'token MinHashFilter,
+    signature: VecDeque<String>,
+    // first token of the input, spanning all of it.
+    token: Option<OwnedToken<'token>>,
+    done: bool,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> MinHashTokenStream<'token, T> {
+    fn consume(&mut self) {
+        let shingle_size = self.filter.shingle_size.max(1);
+        let mut words = VecDeque::with_capacity(shingle_size);
+        let mut shingles = Vec::new();
+        let mut shingle = String::new();
+        while let Some(token) = self.tail.next() {
+            if !token.is_word() {
+                continue;
+            }
+            match &mut self.token {
+                Some(first) => first.offset_to = token.offset_to,
+                None => self.token = Some(token.to_owned()),
+            }
+            if words.len() == shingle_size {
+                words.pop_front();
+            }
+            words.push_back(token.text.to_string());
+            if words.len() == shingle_size {
+                shingle.clear();
+                for word in &words {
+                    if !shingle.is_empty() {
+                        shingle.push(' ');
+                    }
+                    shingle.push_str(word);
+                }
+                shingles.push(hash(&shingle));
+            }
+        }
+        // inputs shorter than a shingle are hashed as a whole.
+        if shingles.is_empty() && !words.is_empty() {
+            shingles.push(hash(&words.into_iter().collect::<Vec<_>>().join(" ")));
+        }
+
+        self.signature = self.filter.signature(&shingles).into();
+        if let Some(token) = &mut self.token {
+            token.position = 0;
+            token.position_length = 1;
+        }
+    }
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for MinHashTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        if !std::mem::replace(&mut self.done, true) {
+            self.consume();
+        }
+
+        let text = self.signature.pop_front()?;
+        let token = self.token.as_mut()?;
+        token.text.clear();
+        token.text.push_str(&text);
+
+        Some(token.borrowed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn signature(filter: &MinHashFilter, text: &str) -> Vec<String> {
+        let tokenizer = WhitespaceTokenizer {};
+        filter.apply(tokenizer.tokenize(text)).as_iter().map(|token| token.text).collect()
+    }
+
+    fn similarity(filter: &MinHashFilter, a: &str, b: &str) -> f64 {
+        let (a, b) = (signature(filter, a), signature(filter, b));
+        a.iter().zip(&b).filter(|(a, b)| a == b).count() as f64 / a.len() as f64
+    }
+
+    #[test]
+    fn min_hash() {
+        let filter = MinHashFilter { hash_count: 2, bucket_count: 16, shingle_size: 2, ..MinHashFilter::default() };
+        let text = "the quick brown fox jumps over the lazy dog near the river bank";
+        let tokens = signature(&filter, text);
+        assert_eq!(tokens.len(), 32);
+        assert!(tokens[0].starts_with("0_0_") && tokens[16].starts_with("1_0_"));
+        assert_eq!(signature(&filter, text), tokens);
+
+        let near = "the quick brown fox jumps over the lazy cat near the river bank";
+        let other = "completely different words make up this unrelated sentence here";
+        assert!(similarity(&filter, text, near) > similarity(&filter, text, other));
+
+        let filter = MinHashFilter { with_rotation: false, ..filter };
+        assert!(signature(&filter, "fox").len() == 2);
+        assert!(signature(&filter, "").is_empty());
+    }
+}
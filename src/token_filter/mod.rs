@@ -8,6 +8,34 @@ use crate::tokenizer::token_stream::TokenStream;
 
 pub mod token_filter_layer;
 pub mod lower_case;
+pub mod arabic_normalization;
+pub mod persian_normalization;
+pub mod hebrew_normalization;
+pub mod cjk_width;
+pub mod kana_conversion;
+pub mod opencc_conversion;
+pub mod transliteration;
+pub mod keyword_marker;
+pub mod token_channel;
+pub mod conditional;
+pub mod language_router;
+pub mod multiplexer;
+pub mod length;
+pub mod truncate;
+pub mod limit_token_count;
+pub mod unique;
+pub mod number_normalization;
+pub mod date_recognition;
+pub mod emoji;
+pub mod min_hash;
+pub mod fingerprint;
+pub mod hyphenation;
+pub mod sentence_boundary;
+
+/// Default of the options of token filters that are on unless turned off.
+pub(crate) fn enabled() -> bool {
+    true
+}
 
 pub struct BoxTokenFilter(Box<dyn BoxableTokenFilter>);
 
@@ -0,0 +1,155 @@
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::token_filter::token_channel::TokenChannel;
+use crate::token_filter::token_filter_layer::{BoxTokenFilterLayer, TokenFilterLayers};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Runs every token through each of the branches and emits the results
+/// stacked at the position of the original token, e.g. the original, a
+/// folded and a stemmed variant of a word.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MultiplexerFilter {
+    pub branches: Vec<BoxTokenFilterLayer>,
+    /// Emit the unmodified token before the branch results.
+    #[serde(default = "crate::token_filter::enabled")]
+    pub preserve_original: bool,
+    /// Skip results whose text was already emitted for the same token.
+    #[serde(default = "crate::token_filter::enabled")]
+    pub remove_duplicates: bool,
+}
+
+#[typetag::serde]
+impl TokenFilter for MultiplexerFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = MultiplexerTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        MultiplexerTokenStream {
+            tail: token_stream,
+            filter: self,
+            channel: TokenChannel::new(),
+            variants: Variants {
+                tokens: Vec::new(),
+                count: 0,
+            },
+            index: 0,
+        }
+    }
+}
+
+/// Variants of the current token, the allocations are reused across tokens.
+struct Variants<'token> {
+    tokens: Vec<OwnedToken<'token>>,
+    count: usize,
+}
+
+impl<'token> Variants<'token> {
+    fn push(&mut self, token: &BorrowedToken<'_, 'token>, position: usize, remove_duplicates: bool) {
+        if remove_duplicates && self.tokens[..self.count].iter().any(|variant| variant.text == *token.text) {
+            return;
+        }
+        match self.tokens.get_mut(self.count) {
+            Some(variant) => token.clone_into_owned(variant),
+            None => self.tokens.push(token.to_owned()),
+        }
+        self.tokens[self.count].position = position;
+        self.count += 1;
+    }
+}
+
+pub struct MultiplexerTokenStream<'token, T> {
+    filter: &'token MultiplexerFilter,
+    channel: TokenChannel<'token>,
+    variants: Variants<'token>,
+    index: usize,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for MultiplexerTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let remove_duplicates = self.filter.remove_duplicates;
+        loop {
+            if self.index < self.variants.count {
+                self.index += 1;
+                return Some(self.variants.tokens[self.index - 1].borrowed());
+            }
+            self.variants.count = 0;
+            self.index = 0;
+
+            let token = self.tail.next()?;
+            let position = token.position;
+            if self.filter.preserve_original {
+                self.variants.push(&token, position, remove_duplicates);
+            }
+            for branch in &self.filter.branches {
+                self.channel.send(&token);
+                let mut branch = branch.apply_layer(self.channel.receiver());
+                while let Some(variant) = branch.next() {
+                    self.variants.push(&variant, position, remove_duplicates);
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::token_filter::lower_case::LowerCaseFilter;
+    use crate::token_filter::token_filter_layer::BaseLevel;
+    use crate::token_filter::transliteration::{BuiltInRules, TransliterationConfig, TransliterationDirection, TransliterationFilter, TransliterationRules};
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn multiplexer(remove_duplicates: bool) -> MultiplexerFilter {
+        let transliteration = TransliterationFilter::new(TransliterationConfig {
+            rules: TransliterationRules::BuiltIn(BuiltInRules::CyrillicLatinBgnPcgn),
+            direction: TransliterationDirection::Forward,
+            inject: false,
+        }).unwrap();
+
+        MultiplexerFilter {
+            branches: vec![
+                BaseLevel.wrap_dynamic_layer(LowerCaseFilter {}),
+                BaseLevel.wrap_layer(LowerCaseFilter {}).wrap_dynamic_layer(transliteration),
+            ],
+            preserve_original: true,
+            remove_duplicates,
+        }
+    }
+
+    fn apply(filter: &MultiplexerFilter, text: &str) -> Vec<(String, usize)> {
+        let tokenizer = WhitespaceTokenizer {};
+        filter.apply(tokenizer.tokenize(text)).as_iter().map(|token| (token.text, token.position)).collect()
+    }
+
+    #[test]
+    fn multiplexer_variants() {
+        assert_eq!(apply(&multiplexer(true), "Москва City"), [
+            ("Москва".to_string(), 0),
+            ("москва".to_string(), 0),
+            ("moskva".to_string(), 0),
+            ("City".to_string(), 1),
+            ("city".to_string(), 1),
+        ]);
+
+        assert_eq!(apply(&multiplexer(false), "City"), [
+            ("City".to_string(), 0),
+            ("city".to_string(), 0),
+            ("city".to_string(), 0),
+        ]);
+    }
+
+    #[test]
+    fn serialize() {
+        let serialized = serde_json::to_string(&multiplexer(true)).unwrap();
+        let deserialized: MultiplexerFilter = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+
+        let filter: MultiplexerFilter = serde_json::from_str(r#"{"branches":[[{"LowerCaseFilter":{}}]]}"#).unwrap();
+        assert!(filter.preserve_original && filter.remove_duplicates);
+    }
+}
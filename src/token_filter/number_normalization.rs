@@ -0,0 +1,422 @@
+use std::collections::VecDeque;
+use polonius_the_crab::{polonius, polonius_return};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::language::Language;
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Attribute holding the parsed value of a numeric token, e.g. for range queries.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NumericValue(pub f64);
+
+/// Converts Unicode decimal digits to ASCII and normalizes numbers to a
+/// canonical form without grouping and with a `.` decimal separator, e.g.
+/// `1,000.5`, `1 000,5` (French) and `١٬٠٠٠٫٥` all become `1000.5`.
+///
+/// Separators are read according to the token's language. Units and
+/// currency symbols attached to a number (`5kg`, `$20`) are split into
+/// tokens of their own. Numeric tokens are tagged with a [`NumericValue`].
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NumberNormalizationFilter {
+    #[serde(default = "crate::token_filter::enabled")]
+    pub split_units: bool,
+}
+
+impl Default for NumberNormalizationFilter {
+    fn default() -> Self {
+        NumberNormalizationFilter {
+            split_units: true
+        }
+    }
+}
+
+#[typetag::serde]
+impl TokenFilter for NumberNormalizationFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = NumberNormalizationTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        NumberNormalizationTokenStream {
+            tail: token_stream,
+            filter: self,
+            queue: VecDeque::new(),
+            lookahead: None,
+            token: None,
+            shift: 0,
+            buffer: String::with_capacity(100),
+        }
+    }
+}
+
+// zero of every range of Unicode decimal digits (general category Nd), sorted.
+const DIGIT_ZEROS: [u32; 68] = [
+    0x0030, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66, 0x0BE6, 0x0C66, 0x0CE6,
+    0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946, 0x19D0, 0x1A80,
+    0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0,
+    0xFF10, 0x104A0, 0x10D30, 0x11066, 0x110F0, 0x11136, 0x111D0, 0x112F0, 0x11450, 0x114D0, 0x11650, 0x116C0,
+    0x11730, 0x118E0, 0x11950, 0x11C50, 0x11D50, 0x11DA0, 0x11F50, 0x16A60, 0x16AC0, 0x16B50, 0x1D7CE, 0x1D7D8,
+    0x1D7E2, 0x1D7EC, 0x1D7F6, 0x1E140, 0x1E2F0, 0x1E4F0, 0x1E950, 0x1FBF0,
+];
+
+/// Value of a Unicode decimal digit.
+pub(crate) fn decimal_digit(c: char) -> Option<u32> {
+    if c.is_ascii_digit() {
+        return Some(c as u32 - 0x30);
+    }
+    let code = c as u32;
+    let index = DIGIT_ZEROS.partition_point(|&zero| zero <= code).checked_sub(1)?;
+    let value = code - DIGIT_ZEROS[index];
+    (value < 10).then_some(value)
+}
+
+fn to_ascii_digits(text: &str, output: &mut String) -> bool {
+    if text.is_ascii() {
+        return false;
+    }
+    output.clear();
+    let mut changed = false;
+    for c in text.chars() {
+        match decimal_digit(c) {
+            Some(digit) if !c.is_ascii_digit() => {
+                output.push(char::from_digit(digit, 10).unwrap());
+                changed = true;
+            }
+            _ => output.push(c)
+        }
+    }
+    changed
+}
+
+fn is_currency(c: char) -> bool {
+    matches!(c, '$' | '¢' | '£' | '¤' | '¥' | '\u{058F}' | '\u{060B}' | '\u{09F2}' | '\u{09F3}' | '\u{0E3F}' | '\u{20A0}'..='\u{20CF}' | '\u{FDFC}')
+}
+
+fn is_unit(text: &str) -> bool {
+    text.chars().all(|c| c.is_alphabetic() || is_currency(c) || matches!(c, '%' | '‰' | '°' | '/' | '²' | '³'))
+}
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '.' | ',' | '\'' | '’' | '\u{00A0}' | '\u{202F}' | '\u{066B}' | '\u{066C}')
+}
+
+/// Decimal separator conventionally used by a language.
+fn decimal_separator(language: Language) -> char {
+    use Language::*;
+    match language {
+        Ara | Pes | Fas | Urd => '\u{066B}',
+        Deu | Fra | Spa | Por | Ita | Rus | Ukr | Bel | Pol | Ces | Slk | Nld | Dan | Nob | Nno | Swe | Fin
+        | Isl | Tur | Aze | Kaz | Uzb | Ron | Hun | Bul | Mkd | Srp | Hrv | Bos | Slv | Lit | Lav | Est
+        | Ell | Ind | Vie | Cat | Eus | Afr | Sqi | Kat | Hye | Epo | Mon => ',',
+        _ => '.'
+    }
+}
+
+/// Languages writing digit groups separated by spaces, `1 000 000`.
+fn groups_with_spaces(language: Language) -> bool {
+    decimal_separator(language) == ','
+}
+
+/// Parses a number written with grouping and decimal separators into its
+/// canonical form. Returns `None` if the separators don't form a valid number.
+fn parse_number(text: &str, decimal: char, output: &mut String) -> Option<f64> {
+    output.clear();
+    let (sign, digits) = match text.chars().next()? {
+        '-' | '\u{2212}' => ("-", &text[text.chars().next()?.len_utf8()..]),
+        '+' => ("", &text[1..]),
+        _ => ("", text)
+    };
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) || !digits.ends_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let decimal = if digits.contains('\u{066B}') {
+        Some('\u{066B}')
+    } else {
+        match (digits.rfind('.'), digits.rfind(',')) {
+            (Some(dot), Some(comma)) => Some(if dot > comma { '.' } else { ',' }),
+            (Some(_), None) => single_decimal(digits, '.', decimal),
+            (None, Some(_)) => single_decimal(digits, ',', decimal),
+            (None, None) => None
+        }
+    };
+
+    output.push_str(sign);
+    let (integer, fraction) = match decimal {
+        Some(decimal) => digits.rsplit_once(decimal)?,
+        None => (digits, "")
+    };
+    // every group after the first has exactly three digits.
+    let mut groups = integer.split(is_separator);
+    let first = groups.next()?;
+    if first.is_empty() || first.len() > 3 && integer.len() != first.len() {
+        return None;
+    }
+    output.push_str(first);
+    for group in groups {
+        if group.len() != 3 {
+            return None;
+        }
+        output.push_str(group);
+    }
+    if !fraction.is_empty() {
+        if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        output.push('.');
+        output.push_str(fraction);
+    }
+
+    output.parse().ok()
+}
+
+// a separator occurring once is the decimal one if the language uses it so, or if it can't be grouping.
+fn single_decimal(digits: &str, separator: char, decimal: char) -> Option<char> {
+    if digits.matches(separator).count() > 1 {
+        return None;
+    }
+    let (_, after) = digits.rsplit_once(separator)?;
+    let is_group = after.len() == 3 && after.bytes().all(|b| b.is_ascii_digit());
+    (separator == decimal || !is_group).then_some(separator)
+}
+
+pub struct NumberNormalizationTokenStream<'token, T> {
+    filter: &'token NumberNormalizationFilter,
+    // tokens produced by splitting a number, emitted before pulling new ones.
+    queue: VecDeque<OwnedToken<'token>>,
+    // token pulled while looking for further space separated digit groups.
+    lookahead: Option<OwnedToken<'token>>,
+    token: Option<OwnedToken<'token>>,
+    // change of positions caused by merged and split tokens.
+    shift: isize,
+    buffer: String,
+    tail: T,
+}
+
+fn starts_numeric(text: &str) -> bool {
+    text.trim_start_matches(is_currency)
+        .trim_start_matches(['-', '+', '\u{2212}'])
+        .starts_with(|c: char| decimal_digit(c).is_some())
+}
+
+// length in bytes of the leading sign, digits and separators, ending with a digit.
+fn number_len(text: &str) -> usize {
+    let mut end = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            c if decimal_digit(c).is_some() => end = index + c.len_utf8(),
+            '-' | '+' | '\u{2212}' if index == 0 => {}
+            ' ' => {}
+            c if is_separator(c) => {}
+            _ => break
+        }
+    }
+    end
+}
+
+impl<'token, T: TokenStream<'token>> NumberNormalizationTokenStream<'token, T> {
+    fn shifted(&self, position: usize) -> usize {
+        position.wrapping_add_signed(self.shift)
+    }
+
+    // merges the following space separated digit groups, `1 000 000,5`.
+    fn merge_groups(&mut self, number: &mut OwnedToken<'token>) {
+        if !groups_with_spaces(number.language()) {
+            return;
+        }
+        loop {
+            let ends_with_group = number.text.rsplit(' ').next().is_some_and(|group| (1..=3).contains(&group.chars().count()) && group.chars().all(|c| decimal_digit(c).is_some()));
+            if !ends_with_group || number.text.starts_with(is_currency) {
+                return;
+            }
+            let Some(next) = self.tail.next() else {
+                return;
+            };
+            let mut chars = next.text.chars();
+            let continues = next.offset_from == number.offset_to + 1
+                && chars.by_ref().take(3).filter(|&c| decimal_digit(c).is_some()).count() == 3
+                && chars.next().is_none_or(|c| is_separator(c) || c.is_alphabetic());
+            if !continues || next.is_keyword() {
+                self.lookahead = Some(next.to_owned());
+                return;
+            }
+            number.text.push(' ');
+            number.text.push_str(next.text);
+            number.offset_to = next.offset_to;
+            self.shift -= 1;
+        }
+    }
+
+    // splits the number into currency prefix, number and unit tokens and queues them.
+    // The digits are still those of the original text, so that the split falls on its offsets.
+    fn process(&mut self, mut number: OwnedToken<'token>) {
+        self.merge_groups(&mut number);
+
+        let language = number.language();
+        let text = std::mem::take(&mut number.text);
+        let prefix_len = text.len() - text.trim_start_matches(is_currency).len();
+        let number_len = prefix_len + number_len(&text[prefix_len..]);
+        let (prefix, rest) = text.split_at(prefix_len);
+        let (digits, unit) = rest.split_at(number_len - prefix_len);
+
+        let mut digits = digits.replace(' ', "\u{00A0}");
+        if to_ascii_digits(&digits, &mut self.buffer) {
+            std::mem::swap(&mut digits, &mut self.buffer);
+        }
+        let value = parse_number(&digits, decimal_separator(language), &mut self.buffer);
+        let split = self.filter.split_units && value.is_some() && (prefix.is_empty() || unit.is_empty()) && is_unit(unit);
+        if !split {
+            match value.filter(|_| unit.is_empty() && prefix.is_empty()) {
+                Some(value) => {
+                    number.text.clone_from(&self.buffer);
+                    number.attributes.insert(NumericValue(value));
+                }
+                None => {
+                    number.text = text;
+                    if to_ascii_digits(&number.text, &mut self.buffer) {
+                        std::mem::swap(&mut number.text, &mut self.buffer);
+                    }
+                }
+            }
+            self.queue.push_back(number);
+            return;
+        }
+
+        let position = number.position;
+        let push = |text: &str, from: usize, to: usize, value: Option<f64>, queue: &mut VecDeque<OwnedToken<'token>>| {
+            let mut token = number.clone();
+            token.text = text.to_string();
+            token.offset_from = (number.offset_from + from).min(number.offset_to);
+            token.offset_to = (number.offset_from + to).min(number.offset_to);
+            token.position = position + queue.len();
+            if let Some(value) = value {
+                token.attributes.insert(NumericValue(value));
+            }
+            queue.push_back(token);
+        };
+        if !prefix.is_empty() {
+            push(prefix, 0, prefix_len, None, &mut self.queue);
+        }
+        push(&self.buffer, prefix_len, number_len, value, &mut self.queue);
+        if !unit.is_empty() {
+            push(unit, number_len, text.len(), None, &mut self.queue);
+        }
+        self.shift += self.queue.len() as isize - 1;
+    }
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for NumberNormalizationTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        if this.queue.is_empty() {
+            let number = match this.lookahead.take() {
+                Some(mut token) => {
+                    token.position = this.shifted(token.position);
+                    if token.is_keyword() || !starts_numeric(&token.text) {
+                        if !token.is_keyword() && to_ascii_digits(&token.text, &mut this.buffer) {
+                            std::mem::swap(&mut token.text, &mut this.buffer);
+                        }
+                        this.token = Some(token);
+                        return this.token.as_mut().map(OwnedToken::borrowed);
+                    }
+                    token
+                }
+                None => polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                    let Some(mut token) = this.tail.next() else {
+                        polonius_return!(None);
+                    };
+                    token.position = token.position.wrapping_add_signed(this.shift);
+                    if token.is_keyword() {
+                        polonius_return!(Some(token));
+                    }
+                    if !starts_numeric(token.text) {
+                        if to_ascii_digits(token.text, &mut this.buffer) {
+                            std::mem::swap(token.text, &mut this.buffer);
+                        }
+                        polonius_return!(Some(token));
+                    }
+                    token.to_owned()
+                })
+            };
+            this.process(number);
+        }
+
+        this.token = this.queue.pop_front();
+        this.token.as_mut().map(OwnedToken::borrowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::script::Script;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn normalize(text: &str, language: Language) -> Vec<(String, usize, Option<f64>)> {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = NumberNormalizationFilter::default();
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, language, Script::Latin)));
+
+        stream.as_iter()
+            .map(|token| {
+                let value = token.attributes.get::<NumericValue>().map(|value| value.0);
+                (token.text, token.position, value)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(normalize("1,000.5 1.5 12,345,678 v1.2.3", Language::Eng), [
+            ("1000.5".to_string(), 0, Some(1000.5)),
+            ("1.5".to_string(), 1, Some(1.5)),
+            ("12345678".to_string(), 2, Some(12345678.0)),
+            ("v1.2.3".to_string(), 3, None),
+        ]);
+        assert_eq!(normalize("1 000,5 € 1.000 -2,5", Language::Fra), [
+            ("1000.5".to_string(), 0, Some(1000.5)),
+            ("€".to_string(), 1, None),
+            ("1000".to_string(), 2, Some(1000.0)),
+            ("-2.5".to_string(), 3, Some(-2.5)),
+        ]);
+        assert_eq!(normalize("١٬٠٠٠٫٥ ४२ 12.05.2024", Language::Ara), [
+            ("1000.5".to_string(), 0, Some(1000.5)),
+            ("42".to_string(), 1, Some(42.0)),
+            ("12.05.2024".to_string(), 2, None),
+        ]);
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(normalize("5kg of apples for $20 or 5 kg", Language::Eng), [
+            ("5".to_string(), 0, Some(5.0)),
+            ("kg".to_string(), 1, None),
+            ("of".to_string(), 2, None),
+            ("apples".to_string(), 3, None),
+            ("for".to_string(), 4, None),
+            ("$".to_string(), 5, None),
+            ("20".to_string(), 6, Some(20.0)),
+            ("or".to_string(), 7, None),
+            ("5".to_string(), 8, Some(5.0)),
+            ("kg".to_string(), 9, None),
+        ]);
+
+        // the split follows the original Arabic-Indic digits, two bytes each.
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = NumberNormalizationFilter::default();
+        let text = "٥kg ٥٠كغ";
+        let tokens: Vec<_> = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Ara, Script::Arabic)))
+            .as_iter()
+            .map(|token| (token.text, &text[token.offset_from..token.offset_to]))
+            .collect();
+        assert_eq!(tokens, [
+            ("5".to_string(), "٥"),
+            ("kg".to_string(), "kg"),
+            ("50".to_string(), "٥٠"),
+            ("كغ".to_string(), "كغ"),
+        ]);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
+use schemars::schema::Schema;
+use serde_derive::{Deserialize, Serialize};
+use crate::script::Script;
+use crate::token::BorrowedToken;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum ChineseConversion {
+    SimplifiedToTraditional,
+    TraditionalToSimplified,
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum ChineseVariant {
+    #[default]
+    Standard,
+    Taiwan,
+    HongKong,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OpenCcConversionConfig {
+    /// Directory containing the OpenCC `.txt` dictionaries (`STPhrases.txt`, `TSCharacters.txt`, ...).
+    pub dictionary_dir: PathBuf,
+    pub conversion: ChineseConversion,
+    #[serde(default)]
+    pub variant: ChineseVariant,
+    /// Also apply the regional idiom dictionaries (`TWPhrases.txt`), only used for Taiwan.
+    #[serde(default)]
+    pub regional_phrases: bool,
+}
+
+/// Converts Han tokens between Traditional and Simplified Chinese using
+/// OpenCC dictionaries. Every conversion stage matches the longest phrase
+/// first and falls back to the character dictionary.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "OpenCcConversionConfig", into = "OpenCcConversionConfig")]
+pub struct OpenCcConversionFilter {
+    config: OpenCcConversionConfig,
+    converter: Arc<OpenCcConverter>,
+}
+
+impl OpenCcConversionFilter {
+    pub fn new(config: OpenCcConversionConfig) -> io::Result<Self> {
+        let converter = OpenCcConverter::load(&config)?;
+        Ok(OpenCcConversionFilter {
+            config,
+            converter: Arc::new(converter)
+        })
+    }
+}
+
+impl TryFrom<OpenCcConversionConfig> for OpenCcConversionFilter {
+    type Error = io::Error;
+
+    fn try_from(config: OpenCcConversionConfig) -> Result<Self, Self::Error> {
+        OpenCcConversionFilter::new(config)
+    }
+}
+
+impl From<OpenCcConversionFilter> for OpenCcConversionConfig {
+    fn from(filter: OpenCcConversionFilter) -> Self {
+        filter.config
+    }
+}
+
+impl JsonSchema for OpenCcConversionFilter {
+    fn schema_name() -> String {
+        "OpenCcConversionFilter".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        OpenCcConversionConfig::json_schema(gen)
+    }
+}
+
+#[typetag::serde]
+impl TokenFilter for OpenCcConversionFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = OpenCcConversionTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        OpenCcConversionTokenStream {
+            tail: token_stream,
+            converter: &self.converter,
+            buffer: String::with_capacity(100)
+        }
+    }
+}
+
+/// A single OpenCC dictionary: each line maps a key to one or more space separated
+/// candidates, the first candidate is used.
+struct Dictionary {
+    entries: HashMap<String, String>,
+    max_key_chars: usize,
+}
+
+impl Dictionary {
+    fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+        let mut entries = HashMap::new();
+        let mut max_key_chars = 0;
+        for line in content.lines() {
+            let Some((key, values)) = line.split_once('\t') else {
+                continue;
+            };
+            let Some(value) = values.split_whitespace().next() else {
+                continue;
+            };
+            max_key_chars = max_key_chars.max(key.chars().count());
+            entries.insert(key.to_string(), value.to_string());
+        }
+
+        Ok(Dictionary { entries, max_key_chars })
+    }
+
+    // returns the longest entry matching a prefix of text together with the matched byte length.
+    fn longest_match(&self, text: &str) -> Option<(usize, &str)> {
+        let ends = text.char_indices()
+            .map(|(index, c)| index + c.len_utf8())
+            .take(self.max_key_chars)
+            .collect::<Vec<_>>();
+        ends.into_iter()
+            .rev()
+            .find_map(|end| self.entries.get(&text[..end]).map(|value| (end, value.as_str())))
+    }
+}
+
+/// Phrases are always tried before single characters.
+struct ConversionStage {
+    phrases: Option<Dictionary>,
+    characters: Dictionary,
+}
+
+impl ConversionStage {
+    fn load(dir: &Path, phrases: Option<&str>, characters: &str) -> io::Result<Self> {
+        Ok(ConversionStage {
+            phrases: phrases.map(|phrases| Dictionary::load(&dir.join(phrases))).transpose()?,
+            characters: Dictionary::load(&dir.join(characters))?,
+        })
+    }
+
+    fn convert(&self, text: &str, output: &mut String) {
+        output.clear();
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            let matched = self.phrases.as_ref()
+                .and_then(|phrases| phrases.longest_match(rest))
+                .or_else(|| self.characters.longest_match(rest));
+            match matched {
+                Some((len, value)) => {
+                    output.push_str(value);
+                    rest = &rest[len..];
+                }
+                None => {
+                    output.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+    }
+}
+
+struct OpenCcConverter {
+    stages: Vec<ConversionStage>,
+}
+
+impl OpenCcConverter {
+    fn load(config: &OpenCcConversionConfig) -> io::Result<Self> {
+        let dir = config.dictionary_dir.as_path();
+        let regional_phrases = config.regional_phrases && config.variant == ChineseVariant::Taiwan;
+        let mut stages = Vec::new();
+        match config.conversion {
+            ChineseConversion::SimplifiedToTraditional => {
+                stages.push(ConversionStage::load(dir, Some("STPhrases.txt"), "STCharacters.txt")?);
+                if regional_phrases {
+                    stages.push(ConversionStage::load(dir, None, "TWPhrases.txt")?);
+                }
+                match config.variant {
+                    ChineseVariant::Standard => {}
+                    ChineseVariant::Taiwan => stages.push(ConversionStage::load(dir, None, "TWVariants.txt")?),
+                    ChineseVariant::HongKong => stages.push(ConversionStage::load(dir, None, "HKVariants.txt")?),
+                }
+            }
+            ChineseConversion::TraditionalToSimplified => {
+                match config.variant {
+                    ChineseVariant::Standard => {}
+                    ChineseVariant::Taiwan => stages.push(ConversionStage::load(dir, Some("TWVariantsRevPhrases.txt"), "TWVariantsRev.txt")?),
+                    ChineseVariant::HongKong => stages.push(ConversionStage::load(dir, Some("HKVariantsRevPhrases.txt"), "HKVariantsRev.txt")?),
+                }
+                if regional_phrases {
+                    stages.push(ConversionStage::load(dir, None, "TWPhrasesRev.txt")?);
+                }
+                stages.push(ConversionStage::load(dir, Some("TSPhrases.txt"), "TSCharacters.txt")?);
+            }
+        }
+
+        Ok(OpenCcConverter { stages })
+    }
+
+    fn convert(&self, text: &mut String, buffer: &mut String) {
+        for stage in &self.stages {
+            stage.convert(text, buffer);
+            mem::swap(text, buffer);
+        }
+    }
+}
+
+pub struct OpenCcConversionTokenStream<'token, T> {
+    buffer: String,
+    converter: &'token OpenCcConverter,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for OpenCcConversionTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let token = self.tail.next()?;
+        if !token.is_keyword() && !token.text.is_ascii() && token.script() == Script::Han {
+            self.converter.convert(token.text, &mut self.buffer);
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::Language;
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn dictionary_dir() -> PathBuf {
+        PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/opencc"))
+    }
+
+    fn convert(config: OpenCcConversionConfig, text: &str) -> Vec<String> {
+        let filter = OpenCcConversionFilter::new(config).unwrap();
+        let tokenizer = WhitespaceTokenizer {};
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Cmn, Script::Han)));
+
+        stream.as_iter().map(|token| token.text).collect()
+    }
+
+    #[test]
+    fn traditional_to_simplified() {
+        let config = OpenCcConversionConfig {
+            dictionary_dir: dictionary_dir(),
+            conversion: ChineseConversion::TraditionalToSimplified,
+            variant: ChineseVariant::Standard,
+            regional_phrases: false,
+        };
+
+        assert_eq!(convert(config, "頭髮 發 以後"), ["头发", "发", "以后"]);
+    }
+
+    #[test]
+    fn simplified_to_traditional_phrases_first() {
+        let config = OpenCcConversionConfig {
+            dictionary_dir: dictionary_dir(),
+            conversion: ChineseConversion::SimplifiedToTraditional,
+            variant: ChineseVariant::Taiwan,
+            regional_phrases: false,
+        };
+
+        assert_eq!(convert(config, "头发 发 以后 伪"), ["頭髮", "發", "以後", "偽"]);
+    }
+
+    #[test]
+    fn serialize() {
+        let config = OpenCcConversionConfig {
+            dictionary_dir: dictionary_dir(),
+            conversion: ChineseConversion::TraditionalToSimplified,
+            variant: ChineseVariant::HongKong,
+            regional_phrases: false,
+        };
+        let serialized = serde_json::to_string(&config).unwrap();
+        assert!(serde_json::from_str::<OpenCcConversionFilter>(&serialized).is_err());
+
+        let config = OpenCcConversionConfig {
+            variant: ChineseVariant::Standard,
+            ..config
+        };
+        let serialized = serde_json::to_string(&config).unwrap();
+        let filter: OpenCcConversionFilter = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(serde_json::to_string(&filter).unwrap(), serialized);
+    }
+}
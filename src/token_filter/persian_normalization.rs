@@ -0,0 +1,139 @@
+use std::collections::BTreeSet;
+use std::mem;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::language::Language;
+use crate::script::Script;
+use crate::token::BorrowedToken;
+use crate::token_filter::arabic_normalization::{is_harakah, TATWEEL};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Normalizes Persian text written in Arabic script to the Persian forms:
+/// Arabic yeh and kaf become farsi yeh and keheh, heh variants are unified,
+/// hamza carriers are reduced and diacritics, tatweel and zero width
+/// non-joiners are removed.
+///
+/// Only tokens whose script is [`Script::Arabic`] and whose language is one
+/// of `languages` are touched, leaving e.g. Arabic and Urdu tokens to their
+/// own normalization.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PersianNormalizationFilter {
+    /// Languages of the tokens to normalize, Persian by default. Add
+    /// [`Language::Unknown`] to normalize tokens without a detected language.
+    #[serde(default = "default_languages")]
+    pub languages: BTreeSet<Language>,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub remove_harakat: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub remove_tatweel: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub remove_zero_width_non_joiner: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub normalize_alef: bool,
+    #[serde(default = "crate::token_filter::enabled")]
+    pub normalize_hamza: bool,
+}
+
+fn default_languages() -> BTreeSet<Language> {
+    BTreeSet::from([Language::Pes, Language::Fas])
+}
+
+impl Default for PersianNormalizationFilter {
+    fn default() -> Self {
+        PersianNormalizationFilter {
+            languages: default_languages(),
+            remove_harakat: true,
+            remove_tatweel: true,
+            remove_zero_width_non_joiner: true,
+            normalize_alef: true,
+            normalize_hamza: true,
+        }
+    }
+}
+
+#[typetag::serde]
+impl TokenFilter for PersianNormalizationFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = PersianNormalizationTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        PersianNormalizationTokenStream {
+            tail: token_stream,
+            filter: self,
+            buffer: String::with_capacity(100)
+        }
+    }
+}
+
+impl PersianNormalizationFilter {
+    fn normalize(&self, text: &str, output: &mut String) {
+        output.clear();
+        for c in text.chars() {
+            let c = match c {
+                c if self.remove_harakat && is_harakah(c) => continue,
+                TATWEEL if self.remove_tatweel => continue,
+                '\u{200C}' if self.remove_zero_width_non_joiner => continue,
+                '\u{0622}' | '\u{0623}' | '\u{0625}' | '\u{0671}' if self.normalize_alef => '\u{0627}',
+                '\u{0624}' if self.normalize_hamza => '\u{0648}',
+                '\u{0626}' if self.normalize_hamza => '\u{06CC}',
+                // arabic yeh, alef maksura and yeh barree
+                '\u{064A}' | '\u{0649}' | '\u{06D2}' => '\u{06CC}',
+                '\u{0643}' => '\u{06A9}',
+                // teh marbuta, heh with yeh above, heh goal and ae
+                '\u{0629}' | '\u{06C0}' | '\u{06C1}' | '\u{06D5}' => '\u{0647}',
+                c => c
+            };
+            output.push(c);
+        }
+    }
+}
+
+pub struct PersianNormalizationTokenStream<'token, T> {
+    buffer: String,
+    filter: &'token PersianNormalizationFilter,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for PersianNormalizationTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let token = self.tail.next()?;
+        if !token.is_keyword() && !token.text.is_ascii() && token.script() == Script::Arabic && self.filter.languages.contains(&token.language()) {
+            self.filter.normalize(token.text, &mut self.buffer);
+            mem::swap(token.text, &mut self.buffer);
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::Language;
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn persian_normalization() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = PersianNormalizationFilter::default();
+        let text = "كتاب علي مي\u{200C}خواهم خانۀ";
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Pes, Script::Arabic)));
+
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["کتاب", "علی", "میخواهم", "خانه"]);
+
+        // Arabic is left to its own normalization.
+        let text = "كتاب علي";
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Ara, Script::Arabic)));
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["كتاب", "علي"]);
+
+        let filter: PersianNormalizationFilter = serde_json::from_str(r#"{"languages":["Unknown"]}"#).unwrap();
+        let stream = filter.apply(tokenizer.tokenize(LanguageDetection::new_init(text, Language::Unknown, Script::Arabic)));
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["کتاب", "علی"]);
+    }
+}
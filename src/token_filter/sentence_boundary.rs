@@ -0,0 +1,168 @@
+use std::collections::VecDeque;
+use polonius_the_crab::{polonius, polonius_return};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::{BorrowedToken, OwnedToken, SeparatorKind};
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::sentence::{separate, terminal, SentenceConfig, Terminal};
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Inserts a hard separator between sentences, detected from the tokens
+/// ending with sentence terminals, for tokenizers that keep punctuation either
+/// attached to words or as separate tokens.
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SentenceBoundaryFilter {
+    #[serde(flatten)]
+    pub config: SentenceConfig,
+}
+
+#[typetag::serde]
+impl TokenFilter for SentenceBoundaryFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = SentenceBoundaryTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        SentenceBoundaryTokenStream {
+            tail: token_stream,
+            filter: self,
+            queue: VecDeque::new(),
+            token: None,
+            sentence: Sentence {
+                config: &self.config,
+                word: String::new(),
+                terminal: None,
+            },
+            previous: None,
+            shift: 0,
+        }
+    }
+}
+
+pub struct SentenceBoundaryTokenStream<'token, T> {
+    filter: &'token SentenceBoundaryFilter,
+    // a separator and the first token of the next sentence.
+    queue: VecDeque<OwnedToken<'token>>,
+    token: Option<OwnedToken<'token>>,
+    sentence: Sentence<'token>,
+    // position and end offset of the last token.
+    previous: Option<(usize, usize)>,
+    // change of positions caused by separators.
+    shift: usize,
+    tail: T,
+}
+
+/// End of the current sentence seen so far.
+struct Sentence<'token> {
+    config: &'token SentenceConfig,
+    // text of the last word, for terminals tokenized separately.
+    word: String,
+    // terminal the last token ended with, unless it ended an abbreviation.
+    terminal: Option<Terminal>,
+}
+
+impl Sentence<'_> {
+    fn is_boundary(&self, token: &BorrowedToken) -> bool {
+        match self.terminal {
+            Some(Terminal::STerm) => token.is_word(),
+            // `a.m. today` doesn't end a sentence.
+            Some(Terminal::ATerm) => token.is_word() && !token.text.chars().find(|c| c.is_alphabetic()).is_some_and(char::is_lowercase),
+            None => false,
+        }
+    }
+
+    fn update(&mut self, token: &BorrowedToken) {
+        if token.separator_kind() == Some(SeparatorKind::Hard) {
+            self.terminal = None;
+            return;
+        }
+        let Some(terminal) = terminal(token.text) else {
+            if token.is_word() {
+                self.terminal = None;
+                self.word.clear();
+                self.word.push_str(token.text);
+            }
+            return;
+        };
+        if terminal == Terminal::ATerm {
+            let attached = token.text.chars().any(char::is_alphanumeric);
+            let word = if attached { token.text.as_str() } else { self.word.as_str() };
+            if self.config.is_abbreviation(word, token.language()) {
+                self.terminal = None;
+                return;
+            }
+        }
+        self.terminal = Some(terminal);
+    }
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for SentenceBoundaryTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        if this.queue.is_empty() {
+            polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                let Some(mut token) = this.tail.next() else {
+                    polonius_return!(None);
+                };
+                token.position += this.shift;
+                let boundary = this.sentence.is_boundary(&token);
+                this.sentence.update(&token);
+                match this.previous.filter(|_| boundary) {
+                    Some(previous) => {
+                        let position_gap = this.filter.config.position_gap;
+                        separate(&mut this.queue, &token, previous, position_gap);
+                        this.shift += position_gap;
+                        this.previous = Some((token.position + position_gap, token.offset_to));
+                    }
+                    None => {
+                        this.previous = Some((token.position, token.offset_to));
+                        polonius_return!(Some(token));
+                    }
+                }
+            });
+        }
+
+        this.token = this.queue.pop_front();
+        this.token.as_mut().map(OwnedToken::borrowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::Language;
+    use crate::language_detection::detection::LanguageDetection;
+    use crate::script::Script;
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn apply(text: &str, language: Language) -> Vec<(bool, String, usize)> {
+        let filter = SentenceBoundaryFilter::default();
+        filter.apply(WhitespaceTokenizer {}.tokenize(LanguageDetection::new_init(text, language, Script::Latin)))
+            .as_iter()
+            .map(|token| (token.is_separator(), token.text, token.position))
+            .collect()
+    }
+
+    fn texts(tokens: Vec<(bool, String, usize)>) -> Vec<String> {
+        tokens.into_iter().map(|(separator, text, _)| if separator { "|".to_string() } else { text }).collect()
+    }
+
+    #[test]
+    fn sentence_boundary() {
+        assert_eq!(apply("Hi there. Bye", Language::Eng), [
+            (false, "Hi".to_string(), 0),
+            (false, "there.".to_string(), 1),
+            (true, "".to_string(), 2),
+            (false, "Bye".to_string(), 3),
+        ]);
+        assert_eq!(apply("A! B! C d e", Language::Eng).into_iter().map(|(_, _, position)| position).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            texts(apply("Dr. Smith came at 10 a.m. today. \"Really?\" Yes .", Language::Eng)),
+            ["Dr.", "Smith", "came", "at", "10", "a.m.", "today.", "|", "\"Really?\"", "|", "Yes", "."]
+        );
+        assert_eq!(
+            texts(apply("Es gibt z.B. Äpfel . Und Birnen.", Language::Deu)),
+            ["Es", "gibt", "z.B.", "Äpfel", ".", "|", "Und", "Birnen."]
+        );
+    }
+}
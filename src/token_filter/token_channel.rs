@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::tokenizer::token_stream::TokenStream;
+
+struct Slot<'token> {
+    token: Option<OwnedToken<'token>>,
+    ready: bool,
+}
+
+/// Hands tokens one at a time to filter chains that are applied to a subset
+/// of a token stream. Every receiver ends after the token sent to it, so a
+/// chain is applied anew to a receiver for each [`TokenChannel::send`] and
+/// drained, keeping no state from one token to the next.
+#[derive(Clone)]
+pub struct TokenChannel<'token> {
+    slot: Rc<RefCell<Slot<'token>>>,
+}
+
+impl<'token> TokenChannel<'token> {
+    pub fn new() -> Self {
+        TokenChannel {
+            slot: Rc::new(RefCell::new(Slot { token: None, ready: false }))
+        }
+    }
+
+    pub fn send(&self, token: &BorrowedToken<'_, 'token>) {
+        let mut slot = self.slot.borrow_mut();
+        token.clone_into_slot(&mut slot.token);
+        slot.ready = true;
+    }
+
+    pub fn receiver(&self) -> ChannelTokenStream<'token> {
+        ChannelTokenStream {
+            slot: Rc::clone(&self.slot),
+            token: None,
+        }
+    }
+}
+
+impl<'token> Default for TokenChannel<'token> {
+    fn default() -> Self {
+        TokenChannel::new()
+    }
+}
+
+pub struct ChannelTokenStream<'token> {
+    slot: Rc<RefCell<Slot<'token>>>,
+    token: Option<OwnedToken<'token>>,
+}
+
+impl<'token> TokenStream<'token> for ChannelTokenStream<'token> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut slot = self.slot.borrow_mut();
+        if !mem::take(&mut slot.ready) {
+            return None;
+        }
+        // the previously received token goes back to the slot to be reused by the next send.
+        mem::swap(&mut slot.token, &mut self.token);
+        drop(slot);
+
+        self.token.as_mut().map(OwnedToken::borrowed)
+    }
+}
+
+impl<'token> Drop for ChannelTokenStream<'token> {
+    fn drop(&mut self) {
+        // a receiver is dropped after each token, its token goes back to the slot as well.
+        let mut slot = self.slot.borrow_mut();
+        if slot.token.is_none() {
+            slot.token = self.token.take();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn reuse() {
+        let tokenizer = WhitespaceTokenizer {};
+        let mut tokens = tokenizer.tokenize("one two");
+        let channel = TokenChannel::new();
+
+        channel.send(&tokens.next().unwrap());
+        let mut receiver = channel.receiver();
+        assert_eq!(receiver.next().map(|token| token.text.clone()), Some("one".to_string()));
+        assert!(receiver.next().is_none());
+        assert!(channel.slot.borrow().token.is_none());
+        drop(receiver);
+        let buffer = channel.slot.borrow().token.as_ref().map(|token| token.text.as_ptr());
+        assert!(buffer.is_some());
+
+        // the next send copies into the token given back.
+        channel.send(&tokens.next().unwrap());
+        let mut receiver = channel.receiver();
+        let token = receiver.next().unwrap();
+        assert_eq!((token.text.as_str(), Some(token.text.as_ptr())), ("two", buffer));
+    }
+}
@@ -115,7 +115,7 @@ impl TokenFilterLayers for BoxTokenFilterLayer {
 
 impl Clone for BoxTokenFilterLayer {
     fn clone(&self) -> Self {
-        BoxableLayer::box_clone(self)
+        self.0.box_clone()
     }
 }
 
@@ -0,0 +1,240 @@
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::PathBuf;
+use std::sync::Arc;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
+use schemars::schema::Schema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::{BorrowedToken, OwnedToken};
+use crate::token_filter::TokenFilter;
+use crate::token_filter::transliteration::rules::{Direction, Transliterator};
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+pub mod rules;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum BuiltInRules {
+    /// BGN/PCGN romanization of Russian, Ukrainian and Belarusian.
+    CyrillicLatinBgnPcgn,
+    /// ISO 9 romanization, reversible.
+    CyrillicLatinIso9,
+    GreekLatin,
+    ArabicLatin,
+    HebrewLatin,
+}
+
+impl BuiltInRules {
+    fn rules(self) -> &'static str {
+        match self {
+            BuiltInRules::CyrillicLatinBgnPcgn => include_str!("rules/cyrillic_latin_bgn_pcgn.txt"),
+            BuiltInRules::CyrillicLatinIso9 => include_str!("rules/cyrillic_latin_iso9.txt"),
+            BuiltInRules::GreekLatin => include_str!("rules/greek_latin.txt"),
+            BuiltInRules::ArabicLatin => include_str!("rules/arabic_latin.txt"),
+            BuiltInRules::HebrewLatin => include_str!("rules/hebrew_latin.txt"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub enum TransliterationRules {
+    BuiltIn(BuiltInRules),
+    /// Path to a file written in the [rule language](rules).
+    File(PathBuf),
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum TransliterationDirection {
+    #[default]
+    Forward,
+    Reverse,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TransliterationConfig {
+    pub rules: TransliterationRules,
+    #[serde(default)]
+    pub direction: TransliterationDirection,
+    /// Keep the original token and emit the transliteration after it at the same position.
+    #[serde(default)]
+    pub inject: bool,
+}
+
+/// Transliterates tokens with a rule set, e.g. `Москва` to `Moskva`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "TransliterationConfig", into = "TransliterationConfig")]
+pub struct TransliterationFilter {
+    config: TransliterationConfig,
+    transliterator: Arc<Transliterator>,
+}
+
+impl TransliterationFilter {
+    pub fn new(config: TransliterationConfig) -> io::Result<Self> {
+        let direction = match config.direction {
+            TransliterationDirection::Forward => Direction::Forward,
+            TransliterationDirection::Reverse => Direction::Reverse,
+        };
+        let transliterator = match &config.rules {
+            TransliterationRules::BuiltIn(rules) => Transliterator::parse(rules.rules(), direction)?,
+            TransliterationRules::File(path) => {
+                let rules = fs::read_to_string(path)
+                    .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+                Transliterator::parse(&rules, direction)?
+            }
+        };
+
+        Ok(TransliterationFilter {
+            config,
+            transliterator: Arc::new(transliterator)
+        })
+    }
+}
+
+impl TryFrom<TransliterationConfig> for TransliterationFilter {
+    type Error = io::Error;
+
+    fn try_from(config: TransliterationConfig) -> Result<Self, Self::Error> {
+        TransliterationFilter::new(config)
+    }
+}
+
+impl From<TransliterationFilter> for TransliterationConfig {
+    fn from(filter: TransliterationFilter) -> Self {
+        filter.config
+    }
+}
+
+impl JsonSchema for TransliterationFilter {
+    fn schema_name() -> String {
+        "TransliterationFilter".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        TransliterationConfig::json_schema(gen)
+    }
+}
+
+#[typetag::serde]
+impl TokenFilter for TransliterationFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = TransliterationTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        TransliterationTokenStream {
+            tail: token_stream,
+            filter: self,
+            buffer: String::with_capacity(100),
+            token: None,
+            pending: false,
+        }
+    }
+}
+
+pub struct TransliterationTokenStream<'token, T> {
+    buffer: String,
+    filter: &'token TransliterationFilter,
+    token: Option<OwnedToken<'token>>,
+    pending: bool,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for TransliterationTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        if mem::take(&mut self.pending) {
+            return self.token.as_mut().map(OwnedToken::borrowed);
+        }
+
+        let token = self.tail.next()?;
+        if token.is_keyword()
+            || !self.filter.transliterator.transliterate(token.text, &mut self.buffer) || *token.text == self.buffer {
+            return Some(token);
+        }
+
+        if self.filter.config.inject {
+            let transliterated = token.clone_into_slot(&mut self.token);
+            mem::swap(&mut transliterated.text, &mut self.buffer);
+            self.pending = true;
+        } else {
+            mem::swap(token.text, &mut self.buffer);
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    fn transliterate(config: TransliterationConfig, text: &str) -> Vec<(String, usize)> {
+        let filter = TransliterationFilter::new(config).unwrap();
+        let tokenizer = WhitespaceTokenizer {};
+        let stream = filter.apply(tokenizer.tokenize(text));
+
+        stream.as_iter().map(|token| (token.text, token.position)).collect()
+    }
+
+    fn texts(tokens: Vec<(String, usize)>) -> Vec<String> {
+        tokens.into_iter().map(|(text, _)| text).collect()
+    }
+
+    #[test]
+    fn built_in_rules() {
+        let config = |rules| TransliterationConfig {
+            rules: TransliterationRules::BuiltIn(rules),
+            direction: TransliterationDirection::Forward,
+            inject: false,
+        };
+
+        assert_eq!(texts(transliterate(config(BuiltInRules::CyrillicLatinBgnPcgn), "Москва Ельцин объём ЩИ")), ["Moskva", "Yel’tsin", "ob”yëm", "SHCHI"]);
+        assert_eq!(texts(transliterate(config(BuiltInRules::CyrillicLatinIso9), "Щука Чехов")), ["Ŝuka", "Čehov"]);
+        assert_eq!(texts(transliterate(config(BuiltInRules::GreekLatin), "Αθήνα μπαρ Ευρώπη")), ["Athina", "bar", "Evropi"]);
+        assert_eq!(texts(transliterate(config(BuiltInRules::ArabicLatin), "القاهرة")), ["alqahrh"]);
+        assert_eq!(texts(transliterate(config(BuiltInRules::HebrewLatin), "שָׁלוֹם")), ["shalom"]);
+    }
+
+    #[test]
+    fn reverse() {
+        let config = TransliterationConfig {
+            rules: TransliterationRules::BuiltIn(BuiltInRules::CyrillicLatinIso9),
+            direction: TransliterationDirection::Reverse,
+            inject: false,
+        };
+
+        assert_eq!(texts(transliterate(config, "Ŝuka Čehov")), ["Щука", "Чехов"]);
+    }
+
+    #[test]
+    fn inject() {
+        let config = TransliterationConfig {
+            rules: TransliterationRules::BuiltIn(BuiltInRules::CyrillicLatinBgnPcgn),
+            direction: TransliterationDirection::Forward,
+            inject: true,
+        };
+
+        assert_eq!(transliterate(config, "Москва city"), [
+            ("Москва".to_string(), 0),
+            ("Moskva".to_string(), 0),
+            ("city".to_string(), 1),
+        ]);
+    }
+
+    #[test]
+    fn serialize() {
+        let config = TransliterationConfig {
+            rules: TransliterationRules::BuiltIn(BuiltInRules::GreekLatin),
+            direction: TransliterationDirection::Forward,
+            inject: true,
+        };
+        let filter = TransliterationFilter::new(config).unwrap();
+        let serialized = serde_json::to_string(&filter).unwrap();
+        let deserialized: TransliterationFilter = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+
+        let missing = r#"{"rules":{"File":"/nonexistent/rules.txt"}}"#;
+        assert!(serde_json::from_str::<TransliterationFilter>(missing).is_err());
+    }
+}
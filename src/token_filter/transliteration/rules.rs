@@ -0,0 +1,446 @@
+//! A small transliteration rule language in the spirit of ICU transform rules.
+//!
+//! ```text
+//! # comments run to the end of the line
+//! shch <> щ ;                 # applied in both directions
+//! ^ { е > ye ;                # `^` anchors the before context to the start of the token
+//! [аеёиоуыэюяйъь] { е > ye ;  # sets support ranges (`а-я`), negation (`[^...]`) and `[:L:]` for letters
+//! е > e ;
+//! ы > y ;                     # forward only
+//! ```
+//!
+//! Every rule is `before { source } after <op> target ;` where the contexts are optional and
+//! `<op>` is `>` (forward), `<` (reverse) or `<>` (both). Whitespace is ignored unless quoted
+//! (`' '`), `''` is an apostrophe and `\uXXXX` escapes a code point. Contexts only apply to the
+//! forward direction. Sources are matched case-insensitively and the case of the match is
+//! carried over to the output. The longest matching source wins, ties go to the earlier rule.
+
+use std::collections::HashMap;
+use std::io;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Reverse,
+}
+
+#[derive(Clone, Debug)]
+struct CharSet {
+    negated: bool,
+    letters: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl CharSet {
+    fn contains(&self, c: char) -> bool {
+        let contained = (self.letters && c.is_alphabetic())
+            || self.ranges.iter().any(|&(from, to)| from <= c && c <= to);
+        contained != self.negated
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Element {
+    Char(char),
+    Set(CharSet),
+    Start,
+    End,
+}
+
+impl Element {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Element::Char(expected) => *expected == c,
+            Element::Set(set) => set.contains(c),
+            Element::Start | Element::End => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    before: Vec<Element>,
+    source: Vec<char>,
+    after: Vec<Element>,
+    target: String,
+}
+
+impl Rule {
+    fn matches(&self, lower: &[char], at: usize) -> bool {
+        let end = at + self.source.len();
+        if end > lower.len() || lower[at..end] != self.source[..] {
+            return false;
+        }
+
+        let mut index = at;
+        for element in self.before.iter().rev() {
+            match element {
+                Element::Start if index == 0 => {}
+                element if index > 0 && element.matches(lower[index - 1]) => index -= 1,
+                _ => return false
+            }
+        }
+
+        let mut index = end;
+        for element in &self.after {
+            match element {
+                Element::End if index == lower.len() => {}
+                element if index < lower.len() && element.matches(lower[index]) => index += 1,
+                _ => return false
+            }
+        }
+        true
+    }
+}
+
+/// A compiled rule set for one direction.
+#[derive(Debug)]
+pub struct Transliterator {
+    rules: Vec<Rule>,
+    by_first_char: HashMap<char, Vec<usize>>,
+}
+
+fn lowercase(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => c
+    }
+}
+
+impl Transliterator {
+    pub fn parse(rules: &str, direction: Direction) -> io::Result<Self> {
+        let mut compiled = Vec::new();
+        for (line, statement) in statements(rules) {
+            let parsed = parse_statement(&statement).map_err(|message| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("transliteration rule at line {}: {}", line, message))
+            })?;
+            if let Some(rule) = parsed.into_rule(direction) {
+                compiled.push(rule);
+            }
+        }
+
+        let mut by_first_char: HashMap<char, Vec<usize>> = HashMap::new();
+        for (index, rule) in compiled.iter().enumerate() {
+            by_first_char.entry(rule.source[0]).or_default().push(index);
+        }
+        // longest sources first, the sort is stable so ties keep the rule order.
+        for candidates in by_first_char.values_mut() {
+            candidates.sort_by_key(|&index| std::cmp::Reverse(compiled[index].source.len()));
+        }
+
+        Ok(Transliterator { rules: compiled, by_first_char })
+    }
+
+    /// Writes the transliteration of `text` into `output` and returns whether any rule applied.
+    pub fn transliterate(&self, text: &str, output: &mut String) -> bool {
+        output.clear();
+        let chars: Vec<char> = text.chars().collect();
+        let lower: Vec<char> = chars.iter().copied().map(lowercase).collect();
+        let mut changed = false;
+        let mut index = 0;
+        while index < chars.len() {
+            let rule = self.by_first_char
+                .get(&lower[index])
+                .and_then(|candidates| candidates.iter().map(|&rule| &self.rules[rule]).find(|rule| rule.matches(&lower, index)));
+            let Some(rule) = rule else {
+                output.push(chars[index]);
+                index += 1;
+                continue;
+            };
+
+            changed = true;
+            let end = index + rule.source.len();
+            if !chars[index].is_uppercase() {
+                output.push_str(&rule.target);
+            } else if is_upper_word(&chars, index, end) {
+                output.extend(rule.target.chars().flat_map(char::to_uppercase));
+            } else {
+                let mut target = rule.target.chars();
+                if let Some(first) = target.next() {
+                    output.extend(first.to_uppercase());
+                    output.extend(target);
+                }
+            }
+            index = end;
+        }
+        changed
+    }
+}
+
+// whether the upper case match at chars[from..to] is part of an all upper case word.
+fn is_upper_word(chars: &[char], from: usize, to: usize) -> bool {
+    if to - from > 1 {
+        return chars[from..to].iter().filter(|c| c.is_alphabetic()).all(|c| c.is_uppercase());
+    }
+    let previous = from.checked_sub(1).map(|index| chars[index]);
+    let next = chars.get(to).copied();
+    [previous, next].into_iter().flatten().any(|c| c.is_alphabetic() && c.is_uppercase())
+}
+
+/// Splits rule text into `;` terminated statements, dropping comments.
+/// Returns the line each statement starts on.
+fn statements(rules: &str) -> Vec<(usize, String)> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut start_line = 1;
+    let mut in_quote = false;
+    let mut escaped = false;
+    for (line, text) in rules.lines().enumerate() {
+        for c in text.chars() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '\'' {
+                in_quote = !in_quote;
+            } else if !in_quote && c == '#' {
+                break;
+            } else if !in_quote && c == ';' {
+                if !current.trim().is_empty() {
+                    statements.push((start_line, std::mem::take(&mut current)));
+                }
+                current.clear();
+                continue;
+            }
+            if current.trim().is_empty() {
+                start_line = line + 1;
+            }
+            current.push(c);
+        }
+        current.push('\n');
+    }
+    // a trailing statement without `;` is still parsed so that it reports its error.
+    if !current.trim().is_empty() {
+        statements.push((start_line, current));
+    }
+    statements
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Operator {
+    Forward,
+    Reverse,
+    Both,
+}
+
+struct ParsedRule {
+    before: Vec<Element>,
+    source: String,
+    after: Vec<Element>,
+    operator: Operator,
+    target: String,
+}
+
+impl ParsedRule {
+    fn into_rule(self, direction: Direction) -> Option<Rule> {
+        let (source, target) = match (direction, self.operator) {
+            (Direction::Forward, Operator::Forward | Operator::Both) => {
+                return Some(Rule {
+                    before: self.before,
+                    source: self.source.chars().map(lowercase).collect(),
+                    after: self.after,
+                    target: self.target,
+                });
+            }
+            (Direction::Reverse, Operator::Reverse | Operator::Both) => (self.target, self.source),
+            _ => return None
+        };
+        Some(Rule {
+            before: Vec::new(),
+            source: source.chars().map(lowercase).collect(),
+            after: Vec::new(),
+            target,
+        })
+    }
+}
+
+fn parse_statement(statement: &str) -> Result<ParsedRule, String> {
+    let mut chars = statement.chars().peekable();
+    let mut sides: Vec<Vec<Element>> = vec![Vec::new()];
+    let mut operator = None;
+    let mut left_brace = None;
+    let mut right_brace = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '<' | '>' if operator.is_none() => {
+                operator = Some(match (c, chars.peek()) {
+                    ('<', Some('>')) => {
+                        chars.next();
+                        Operator::Both
+                    }
+                    ('<', _) => Operator::Reverse,
+                    _ => Operator::Forward,
+                });
+                sides.push(Vec::new());
+            }
+            '{' if operator.is_none() && left_brace.is_none() => {
+                left_brace = Some(sides[0].len());
+            }
+            '}' if operator.is_none() && right_brace.is_none() => {
+                right_brace = Some(sides[0].len());
+            }
+            '\'' => {
+                let side = sides.last_mut().unwrap();
+                let mut empty = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            side.push(Element::Char('\''));
+                        }
+                        Some('\'') => break,
+                        Some('\\') => side.push(Element::Char(parse_escape(&mut chars)?)),
+                        Some(c) => side.push(Element::Char(c)),
+                        None => return Err("unterminated quote".to_string())
+                    }
+                    empty = false;
+                }
+                // a lone `''` is an apostrophe.
+                if empty {
+                    side.push(Element::Char('\''));
+                }
+            }
+            '\\' => {
+                let c = parse_escape(&mut chars)?;
+                sides.last_mut().unwrap().push(Element::Char(c));
+            }
+            '[' => {
+                let set = parse_set(&mut chars)?;
+                sides.last_mut().unwrap().push(Element::Set(set));
+            }
+            '^' => sides.last_mut().unwrap().push(Element::Start),
+            '$' => sides.last_mut().unwrap().push(Element::End),
+            c => sides.last_mut().unwrap().push(Element::Char(c)),
+        }
+    }
+
+    let operator = operator.ok_or("missing `>`, `<` or `<>`")?;
+    let target = sides.pop().unwrap();
+    let mut left = sides.pop().unwrap();
+    let right_brace = right_brace.unwrap_or(left.len());
+    let after = left.split_off(right_brace);
+    let source = left.split_off(left_brace.unwrap_or(0));
+    let before = left;
+
+    if before.iter().any(|element| matches!(element, Element::End))
+        || after.iter().any(|element| matches!(element, Element::Start))
+        || before.iter().skip(1).any(|element| matches!(element, Element::Start))
+        || after.iter().rev().skip(1).any(|element| matches!(element, Element::End)) {
+        return Err("`^` must start the before context and `$` must end the after context".to_string());
+    }
+
+    if source.is_empty() {
+        return Err("empty source".to_string());
+    }
+
+    Ok(ParsedRule {
+        before,
+        source: literal(source)?,
+        after,
+        operator,
+        target: literal(target)?,
+    })
+}
+
+fn literal(elements: Vec<Element>) -> Result<String, String> {
+    elements.into_iter()
+        .map(|element| match element {
+            Element::Char(c) => Ok(c),
+            _ => Err("sets and anchors are only allowed in contexts".to_string())
+        })
+        .collect()
+}
+
+fn parse_escape(chars: &mut Peekable<Chars>) -> Result<char, String> {
+    match chars.next() {
+        Some('u') => {
+            let hex: String = chars.by_ref().take(4).collect();
+            u32::from_str_radix(&hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("invalid escape `\\u{}`", hex))
+        }
+        Some(c) => Ok(c),
+        None => Err("dangling `\\`".to_string())
+    }
+}
+
+fn parse_set(chars: &mut Peekable<Chars>) -> Result<CharSet, String> {
+    let mut set = CharSet {
+        negated: false,
+        letters: false,
+        ranges: Vec::new(),
+    };
+    if chars.peek() == Some(&'^') {
+        chars.next();
+        set.negated = true;
+    }
+    loop {
+        let from = match chars.next() {
+            Some(']') => return Ok(set),
+            Some('[') => {
+                let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                if class != ":L:" {
+                    return Err(format!("unsupported class `[{}]`", class));
+                }
+                set.letters = true;
+                continue;
+            }
+            Some('\\') => parse_escape(chars)?,
+            Some(c) => c,
+            None => return Err("unterminated set".to_string())
+        };
+        let mut to = from;
+        if chars.peek() == Some(&'-') {
+            chars.next();
+            to = match chars.next() {
+                Some('\\') => parse_escape(chars)?,
+                Some(']') | None => return Err("unterminated range".to_string()),
+                Some(c) => c,
+            };
+        }
+        set.ranges.push((lowercase(from), lowercase(to)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transliterate(rules: &str, direction: Direction, text: &str) -> String {
+        let transliterator = Transliterator::parse(rules, direction).unwrap();
+        let mut output = String::new();
+        transliterator.transliterate(text, &mut output);
+        output
+    }
+
+    #[test]
+    fn rules() {
+        let rules = "
+            # context sensitive rules come first
+            ^ { е > ye ;
+            [аоу] { е > ye ;
+            а <> a ;
+            е <> e ;
+            щ <> shch ;
+            ш <> sh ;
+            ' ' > '_' ; x } $ > ks ;
+        ";
+        assert_eq!(transliterate(rules, Direction::Forward, "ееае"), "yeeaye");
+        assert_eq!(transliterate(rules, Direction::Forward, "Щеш ЩЕ"), "Shchesh_SHCHE");
+        assert_eq!(transliterate(rules, Direction::Forward, "xx"), "xks");
+        assert_eq!(transliterate(rules, Direction::Reverse, "Shcheshe"), "Щеше");
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(Transliterator::parse("a b ;", Direction::Forward).is_err());
+        assert!(Transliterator::parse("[a] > b ;", Direction::Forward).is_err());
+        assert!(Transliterator::parse("a > 'b ;", Direction::Forward).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::BorrowedToken;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Truncates tokens to at most `length` characters.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TruncateFilter {
+    pub length: usize,
+}
+
+#[typetag::serde]
+impl TokenFilter for TruncateFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = TruncateTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        TruncateTokenStream {
+            tail: token_stream,
+            filter: self,
+        }
+    }
+}
+
+pub struct TruncateTokenStream<'token, T> {
+    filter: &'token TruncateFilter,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for TruncateTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let token = self.tail.next()?;
+        if token.text.len() > self.filter.length {
+            if let Some((index, _)) = token.text.char_indices().nth(self.filter.length) {
+                token.text.truncate(index);
+            }
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn truncate() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = TruncateFilter { length: 3 };
+        let stream = filter.apply(tokenizer.tokenize("ab abcdef ёжики"));
+
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["ab", "abc", "ёжи"]);
+    }
+}
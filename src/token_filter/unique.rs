@@ -0,0 +1,100 @@
+use std::collections::HashSet;
+use polonius_the_crab::{polonius, polonius_return};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use crate::token::BorrowedToken;
+use crate::token_filter::TokenFilter;
+use crate::tokenizer::token_stream::TokenStream;
+use crate::token_filter::TokenFilterRegistry;
+
+/// Removes tokens whose text was already emitted.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UniqueFilter {
+    /// Only remove duplicates stacked at the same position, e.g. the
+    /// variants emitted by a [`MultiplexerFilter`](crate::token_filter::multiplexer::MultiplexerFilter).
+    #[serde(default)]
+    pub only_on_same_position: bool,
+}
+
+#[typetag::serde]
+impl TokenFilter for UniqueFilter {
+    type TokenStream<'token, T: TokenStream<'token> + 'token> = UniqueTokenStream<'token, T>;
+
+    fn apply<'token, T: TokenStream<'token> + 'token>(&'token self, token_stream: T) -> Self::TokenStream<'token, T> {
+        UniqueTokenStream {
+            tail: token_stream,
+            filter: self,
+            seen: HashSet::new(),
+            position: usize::MAX,
+        }
+    }
+}
+
+pub struct UniqueTokenStream<'token, T> {
+    filter: &'token UniqueFilter,
+    seen: HashSet<String>,
+    position: usize,
+    tail: T,
+}
+
+impl<'token, T: TokenStream<'token>> TokenStream<'token> for UniqueTokenStream<'token, T> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        loop {
+            polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                let Some(token) = this.tail.next() else {
+                    polonius_return!(None);
+                };
+                if this.filter.only_on_same_position && token.position != this.position {
+                    this.position = token.position;
+                    this.seen.clear();
+                }
+                if !this.seen.contains(token.text.as_str()) {
+                    this.seen.insert(token.text.clone());
+                    polonius_return!(Some(token));
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::token_filter::lower_case::LowerCaseFilter;
+    use crate::token_filter::multiplexer::MultiplexerFilter;
+    use crate::token_filter::token_filter_layer::{BaseLevel, TokenFilterLayers};
+    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn unique() {
+        let tokenizer = WhitespaceTokenizer {};
+        let filter = UniqueFilter { only_on_same_position: false };
+        let stream = filter.apply(tokenizer.tokenize("the cat and the hat"));
+
+        let tokens: Vec<String> = stream.as_iter().map(|token| token.text).collect();
+        assert_eq!(tokens, ["the", "cat", "and", "hat"]);
+    }
+
+    #[test]
+    fn only_on_same_position() {
+        let tokenizer = WhitespaceTokenizer {};
+        let multiplexer = MultiplexerFilter {
+            branches: vec![BaseLevel.wrap_dynamic_layer(LowerCaseFilter {})],
+            preserve_original: true,
+            remove_duplicates: false,
+        };
+        let filter = UniqueFilter { only_on_same_position: true };
+        let layers = BaseLevel.wrap_layer(multiplexer).wrap_layer(filter);
+        let stream = layers.apply_layer(tokenizer.tokenize("the The the"));
+
+        let tokens: Vec<(String, usize)> = stream.as_iter().map(|token| (token.text, token.position)).collect();
+        assert_eq!(tokens, [
+            ("the".to_string(), 0),
+            ("The".to_string(), 1),
+            ("the".to_string(), 1),
+            ("the".to_string(), 2),
+        ]);
+    }
+}
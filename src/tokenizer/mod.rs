@@ -2,12 +2,12 @@ use schemars::gen::SchemaGenerator;
 use schemars::JsonSchema;
 use schemars::schema::Schema;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use typetag::__private::erased_serde;
 use crate::inline_dyn::Dynamic;
 use crate::language_detection::detection::LanguageDetection;
 use crate::tokenizer::token_stream::TokenStream;
 pub mod token_stream;
 pub mod whitespace_tokenizer;
+pub mod sentence;
 
 pub struct BoxTokenizer(Box<dyn BoxableTokenizer>);
 
@@ -63,7 +63,7 @@ impl<T: Tokenizer + Serialize> BoxableTokenizer for T
 
 impl Serialize for BoxTokenizer {
     fn serialize<S>(&self, mut serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-        erased_serde::serialize(&*self.0, serializer)
+        self.0.serialize(serializer)
     }
 }
 
@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use polonius_the_crab::{polonius, polonius_return};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+use crate::inline_dyn::Dynamic;
+use crate::language::Language;
+use crate::language_detection::detection::LanguageDetection;
+use crate::token::{BorrowedToken, OwnedToken, SeparatorKind, TokenKind};
+use crate::tokenizer::token_stream::TokenStream;
+use crate::tokenizer::{BoxTokenizer, Tokenizer};
+use crate::tokenizer::TokenizerRegistry;
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SentenceConfig {
+    /// Abbreviations per language in addition to the built-in ones, without
+    /// the final dot, e.g. `dr` or `z.b`. Matched case-insensitively.
+    #[serde(default)]
+    pub abbreviations: BTreeMap<Language, BTreeSet<String>>,
+    /// Positions left between the last token of a sentence and the first
+    /// token of the next one. The separator takes the first of them.
+    #[serde(default = "default_position_gap")]
+    pub position_gap: usize,
+}
+
+impl Default for SentenceConfig {
+    fn default() -> Self {
+        SentenceConfig {
+            abbreviations: BTreeMap::new(),
+            position_gap: default_position_gap(),
+        }
+    }
+}
+
+fn default_position_gap() -> usize {
+    1
+}
+
+impl SentenceConfig {
+    /// Whether a dot-terminated word, such as `Dr.` or `z.B.`, is an abbreviation rather than a sentence end.
+    pub fn is_abbreviation(&self, word: &str, language: Language) -> bool {
+        let word = word.trim_start_matches(is_opening);
+        let word = word.strip_suffix(is_a_term).unwrap_or(word);
+        let mut chars = word.chars();
+        // initials, `J. R. R. Tolkien`.
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return c.is_uppercase();
+        }
+
+        let lowercase = word.to_lowercase();
+        builtin_abbreviations(language).contains(&lowercase.as_str())
+            || self.abbreviations.get(&language).is_some_and(|abbreviations| {
+                abbreviations.contains(word) || abbreviations.contains(&lowercase)
+            })
+    }
+
+    /// Byte offsets the sentences of the text after the first one start at,
+    /// by the UAX #29 sentence rules not breaking after abbreviations.
+    pub fn boundaries(&self, text: &str, language: Language) -> VecDeque<usize> {
+        let mut boundaries = VecDeque::new();
+        let mut sentences = text.split_sentence_bound_indices().peekable();
+        while let Some((_, sentence)) = sentences.next() {
+            let Some(&(start, _)) = sentences.peek() else {
+                break;
+            };
+            let last_word = sentence.split_whitespace().last().unwrap_or_default();
+            if terminal(last_word) == Some(Terminal::ATerm) && self.is_abbreviation(last_word, language) {
+                continue;
+            }
+            boundaries.push_back(start);
+        }
+
+        boundaries
+    }
+}
+
+fn builtin_abbreviations(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::Eng => &[
+            "mr", "mrs", "ms", "dr", "prof", "st", "jr", "sr", "vs", "etc", "e.g", "i.e", "inc", "ltd",
+            "co", "corp", "mt", "no", "fig", "approx", "dept", "est", "jan", "feb", "mar", "apr", "jun",
+            "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+        ],
+        Language::Deu => &[
+            "z.b", "d.h", "u.a", "usw", "bzw", "dr", "prof", "nr", "ca", "vgl", "ggf", "evtl", "bspw",
+            "s", "str", "hr", "fr", "inkl", "zzgl", "u.u", "o.ä", "etc",
+        ],
+        Language::Fra => &["m", "mme", "mlle", "dr", "pr", "etc", "p.ex", "cf", "env", "av", "bd", "st", "ste"],
+        Language::Spa => &["sr", "sra", "srta", "dr", "dra", "ud", "uds", "etc", "p.ej", "pág", "núm", "av", "ej"],
+        Language::Ita => &["sig", "sig.ra", "dott", "prof", "ecc", "es", "pag", "n", "ing", "avv"],
+        Language::Por => &["sr", "sra", "dr", "dra", "etc", "pág", "n", "av", "ex", "prof"],
+        Language::Rus => &[
+            "т.е", "т.к", "т.д", "т.п", "т.н", "г", "гг", "др", "пр", "им", "ул", "см", "стр", "руб",
+            "коп", "тыс", "млн", "млрд", "проф", "акад", "им", "д", "кв",
+        ],
+        Language::Ukr => &["т.б", "т.д", "т.п", "т.ч", "г", "рр", "ім", "вул", "див", "стор", "грн", "тис", "млн", "проф"],
+        _ => &[],
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Terminal {
+    /// Dot, which may also end an abbreviation.
+    ATerm,
+    /// Exclamation and question marks.
+    STerm,
+}
+
+fn is_a_term(c: char) -> bool {
+    matches!(c, '.' | '\u{2024}' | '\u{FE52}' | '\u{FF0E}')
+}
+
+fn is_s_term(c: char) -> bool {
+    matches!(c,
+        '!' | '?' | '\u{203C}'..='\u{203D}' | '\u{2047}'..='\u{2049}' | '\u{3002}' | '\u{FF01}' | '\u{FF1F}'
+        | '\u{FF61}' | '\u{061F}' | '\u{06D4}' | '\u{0964}'..='\u{0965}' | '\u{1362}'
+    )
+}
+
+fn is_opening(c: char) -> bool {
+    matches!(c, '"' | '\'' | '(' | '[' | '{' | '«' | '“' | '‘' | '„')
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '}' | '»' | '”' | '’')
+}
+
+/// Sentence terminal a word ends with, ignoring closing quotes and brackets.
+pub(crate) fn terminal(word: &str) -> Option<Terminal> {
+    let mut terminal = None;
+    for c in word.trim_end_matches(is_closing).chars().rev().take_while(|&c| is_a_term(c) || is_s_term(c)) {
+        if is_s_term(c) {
+            return Some(Terminal::STerm);
+        }
+        terminal = Some(Terminal::ATerm);
+    }
+    terminal
+}
+
+/// Queues a hard separator between the previous sentence, ending at `previous`
+/// (position, offset), and the token starting the next one.
+pub(crate) fn separate<'token>(
+    queue: &mut VecDeque<OwnedToken<'token>>,
+    token: &BorrowedToken<'_, 'token>,
+    previous: (usize, usize),
+    position_gap: usize,
+) {
+    let (position, offset) = previous;
+    let mut separator = token.to_owned();
+    separator.text.clear();
+    separator.attributes.clear();
+    separator.token_kind = TokenKind::Separator(SeparatorKind::Hard);
+    separator.offset_from = offset;
+    separator.offset_to = offset;
+    separator.position = position + 1;
+    separator.position_length = 1;
+    queue.push_back(separator);
+
+    let mut token = token.to_owned();
+    token.position += position_gap;
+    queue.push_back(token);
+}
+
+/// Wraps a tokenizer, inserting a hard separator between the tokens of
+/// consecutive sentences.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SentenceTokenizer {
+    pub tokenizer: BoxTokenizer,
+    #[serde(flatten)]
+    pub config: SentenceConfig,
+}
+
+#[typetag::serde]
+impl Tokenizer for SentenceTokenizer {
+    type TokenStream<'token> = SentenceTokenStream<'token>;
+
+    fn tokenize<'token>(&'token self, text: impl Into<LanguageDetection<'token, 'token>>) -> Self::TokenStream<'token> {
+        let detection = text.into();
+        let boundaries = self.config.boundaries(detection.text(), detection.language());
+
+        SentenceTokenStream {
+            tail: self.tokenizer.tokenize(detection),
+            config: &self.config,
+            boundaries,
+            queue: VecDeque::new(),
+            token: None,
+            previous: None,
+            shift: 0,
+        }
+    }
+}
+
+pub struct SentenceTokenStream<'token> {
+    config: &'token SentenceConfig,
+    // offsets the sentences start at.
+    boundaries: VecDeque<usize>,
+    // a separator and the first token of the next sentence.
+    queue: VecDeque<OwnedToken<'token>>,
+    token: Option<OwnedToken<'token>>,
+    // position and end offset of the last token.
+    previous: Option<(usize, usize)>,
+    // change of positions caused by separators.
+    shift: usize,
+    tail: Dynamic<dyn TokenStream<'token> + 'token>,
+}
+
+impl<'token> TokenStream<'token> for SentenceTokenStream<'token> {
+    fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>> {
+        let mut this = self;
+        if this.queue.is_empty() {
+            polonius!(|this| -> Option<BorrowedToken<'polonius, 'token>> {
+                let Some(mut token) = this.tail.next() else {
+                    polonius_return!(None);
+                };
+                token.position += this.shift;
+                let mut boundary = false;
+                while this.boundaries.front().is_some_and(|&start| start <= token.offset_from) {
+                    this.boundaries.pop_front();
+                    boundary = true;
+                }
+                match this.previous.filter(|_| boundary) {
+                    Some(previous) => {
+                        separate(&mut this.queue, &token, previous, this.config.position_gap);
+                        this.shift += this.config.position_gap;
+                        this.previous = Some((token.position + this.config.position_gap, token.offset_to));
+                    }
+                    None => {
+                        this.previous = Some((token.position, token.offset_to));
+                        polonius_return!(Some(token));
+                    }
+                }
+            });
+        }
+
+        this.token = this.queue.pop_front();
+        this.token.as_mut().map(OwnedToken::borrowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::Script;
+    use crate::tokenizer::whitespace_tokenizer::WhitespaceTokenizer;
+    use super::*;
+
+    #[test]
+    fn boundaries() {
+        let config = SentenceConfig::default();
+        let text = "Dr. Smith met J. Doe at 10 a.m. today. Really? Yes! See e.g. the report.";
+        let sentences: Vec<usize> = config.boundaries(text, Language::Eng).into();
+        assert_eq!(sentences, [39, 47, 52]);
+
+        let text = "Das sind z.B. Äpfel. Und Birnen.";
+        assert_eq!(Vec::from(config.boundaries(text, Language::Deu)), [22]);
+        assert_eq!(Vec::from(config.boundaries(text, Language::Eng)), [14, 22]);
+    }
+
+    #[test]
+    fn sentence_tokenizer() {
+        let tokenizer = SentenceTokenizer {
+            tokenizer: BoxTokenizer(Box::new(WhitespaceTokenizer {})),
+            config: SentenceConfig { position_gap: 2, ..SentenceConfig::default() },
+        };
+        let text = "Hello world. Dr. Who? Bye";
+        let tokens: Vec<(bool, String, usize, usize)> = tokenizer.tokenize(LanguageDetection::new_init(text, Language::Eng, Script::Latin))
+            .as_iter()
+            .map(|token| (token.is_separator(), token.text, token.position, token.offset_from))
+            .collect();
+        assert_eq!(tokens, [
+            (false, "Hello".to_string(), 0, 0),
+            (false, "world.".to_string(), 1, 6),
+            (true, "".to_string(), 2, 12),
+            (false, "Dr.".to_string(), 4, 13),
+            (false, "Who?".to_string(), 5, 17),
+            (true, "".to_string(), 6, 21),
+            (false, "Bye".to_string(), 8, 22),
+        ]);
+
+        let serialized = serde_json::to_string(&tokenizer).unwrap();
+        assert_eq!(serialized, r#"{"tokenizer":{"WhitespaceTokenizer":{}},"abbreviations":{},"position_gap":2}"#);
+        let _: SentenceTokenizer = serde_json::from_str(&serialized).unwrap();
+    }
+}
@@ -45,6 +45,7 @@ impl<'token> WhitespaceTokenStream<'token> {
 impl<'token> TokenStream<'token> for WhitespaceTokenStream<'token>{
     fn next<'a>(&'a mut self) -> Option<BorrowedToken<'a, 'token>>{
         self.token.text.clear();
+        self.token.attributes.clear();
         self.token.position = self.token.position.wrapping_add(1);
         while let Some((offset_from, c)) = self.chars.next() {
             if !c.is_ascii_whitespace() {